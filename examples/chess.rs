@@ -1,8 +1,8 @@
-use glam::{vec2, vec3};
+use glam::{vec2, vec3, Vec2};
 use rengine::{
-    input::InputState,
-    renderer::Projection,
-    sprite::{Color, Rect, Sprite},
+    input::{InputState, MouseButton},
+    renderer::ProjectionMode,
+    sprite::{Color, Rect, Sprite, SpriteHandle},
     texture,
     transform::Transform,
     Game,
@@ -14,12 +14,14 @@ fn main() {
 
 #[derive(Default)]
 struct Chess {
-    piece: usize,
+    piece: Option<SpriteHandle>,
 }
 
 impl Game for Chess {
     fn init(&mut self, data: &mut rengine::GameData) {
-        *data.renderer.projection = Projection::FixedMinimum(10.0, 10.0);
+        *data.renderer.main_viewport_mut().projection =
+            ProjectionMode::FixedMinimum(10.0, 10.0).into();
+        data.renderer.main_viewport_mut().letterbox = Some(Color::BLACK);
         data.renderer.background = Color {
             r: 0.1,
             g: 0.1,
@@ -43,16 +45,14 @@ impl Game for Chess {
                 ))],
             )
         }) {
-            data.renderer.sprites.insert(sprite);
+            data.renderer.insert(sprite);
         }
 
-        let pieces = data
-            .texture_manager
-            .load(&texture::TextureSource::Memory(include_bytes!(
-                "ChessPiecesArray.png"
-            )));
+        let pieces = data.texture_manager.load(&texture::TextureSource::memory(
+            include_bytes!("ChessPiecesArray.png").as_slice(),
+        ));
         let sampler = data.texture_manager.linear_sampler();
-        self.piece = data.renderer.sprites.insert(Sprite::new_texture(
+        self.piece = Some(data.renderer.insert(Sprite::new_texture(
             &pieces,
             sampler,
             Some(Rect {
@@ -60,11 +60,21 @@ impl Game for Chess {
                 size: vec2(1.0 / 6.0, 0.5),
             }),
             vec![Transform::default()],
-        ));
+        )));
     }
 
     fn update(&mut self, data: &mut rengine::GameData) {
-        data.renderer.sprites[self.piece].visible = data.input.is_key(' ', InputState::Up);
+        if let Some(piece) = self.piece.and_then(|piece| data.renderer.get_mut(piece)) {
+            piece.visible = data.input.is_key(' ', InputState::Up);
+        }
+        if data.input.is_button(MouseButton::Left, InputState::Pressed) {
+            let square = (data.renderer.screen_to_world(data.input.mouse_pos) + 4.0).floor();
+            if square.cmpge(Vec2::ZERO).all() && square.cmplt(Vec2::splat(8.0)).all() {
+                if let Some(piece) = self.piece.and_then(|piece| data.renderer.get_mut(piece)) {
+                    piece.transforms[0].translation = (square - 3.5).extend(0.0);
+                }
+            }
+        }
         if data.input.is_key('q', InputState::Pressed) {
             data.exit();
         }
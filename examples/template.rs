@@ -1,6 +1,6 @@
 use glam::Vec3;
 use rengine::{
-    renderer::Projection, sprite::Sprite, texture::TextureSource, transform::Transform, Game,
+    assets::Mount, renderer::ProjectionMode, sprite::Sprite, transform::Transform, Game,
 };
 
 fn main() {
@@ -11,13 +11,20 @@ struct Main;
 
 impl Game for Main {
     fn init(&mut self, data: &mut rengine::GameData) {
-        *data.renderer.projection = Projection::FixedHeight(2.0);
+        *data.renderer.main_viewport_mut().projection = ProjectionMode::FixedHeight(2.0).into();
         data.renderer.window.set_title("Rengine Template");
 
-        data.renderer.sprites.insert(Sprite::new_texture(
+        data.assets
+            .mount(
+                "",
+                Mount::embedded([("test.png", include_bytes!("test.png").as_slice())]),
+            )
+            .unwrap();
+        data.renderer.insert(Sprite::new_texture(
             &data
-                .texture_manager
-                .load(&TextureSource::Memory(include_bytes!("test.png"))),
+                .assets
+                .texture(&mut data.texture_manager, "test.png")
+                .unwrap(),
             data.texture_manager.linear_sampler(),
             None,
             vec![
@@ -1,5 +1,5 @@
 use rengine::{
-    renderer::Projection, sprite::Sprite, text::TextStyle, texture::TextureSource,
+    renderer::ProjectionMode, sprite::Sprite, text::TextStyle, texture::TextureSource,
     transform::Transform, Game,
 };
 use wgpu::Color;
@@ -12,19 +12,22 @@ struct Main;
 
 impl Game for Main {
     fn init(&mut self, data: &mut rengine::GameData) {
-        *data.renderer.projection = Projection::FixedHeight(800.0);
+        *data.renderer.main_viewport_mut().projection = ProjectionMode::FixedHeight(800.0).into();
         data.renderer.window.set_title("Rengine Template");
 
         let font = data
             .text_manager
             .load_bytes(include_bytes!("BagnardSans.otf"))
             .unwrap();
-        let texture = data.texture_manager.load(&TextureSource::Image(
-            data.text_manager
-                .make_image(&[TextStyle::new("Foo bar?", 24.0, font)], Color::WHITE)
-                .unwrap(),
-        ));
-        data.renderer.sprites.insert(Sprite::new_texture(
+        let texture = data.texture_manager.load_with_key(
+            "foo bar",
+            &TextureSource::image(
+                data.text_manager
+                    .make_image(&[TextStyle::new("Foo bar?", 24.0, font)], Color::WHITE)
+                    .unwrap(),
+            ),
+        );
+        data.renderer.insert(Sprite::new_texture(
             &texture,
             data.texture_manager.linear_sampler(),
             None,
@@ -0,0 +1,383 @@
+//! Sprite sheet animations.
+
+use std::time::Duration;
+
+use fxhash::FxHashMap;
+use glam::{uvec2, vec2, UVec2};
+
+use crate::{
+    sprite::{Rect, Sprite, SpriteHandle, TextureVertex},
+    GameData,
+};
+
+/// A texture region sliced into a grid of equally sized frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteSheet {
+    /// The area of the texture the grid covers, in texture coordinates.
+    pub region: Rect,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl SpriteSheet {
+    /// Slices the whole texture into a grid.
+    ///
+    /// # Panics
+    /// If `columns` or `rows` is zero.
+    #[must_use]
+    pub fn new(columns: u32, rows: u32) -> Self {
+        assert!(
+            columns > 0 && rows > 0,
+            "sprite sheet needs at least one column and row"
+        );
+        Self {
+            region: Rect::default(),
+            columns,
+            rows,
+        }
+    }
+
+    #[must_use]
+    pub fn with_region(self, region: Rect) -> Self {
+        Self { region, ..self }
+    }
+
+    /// The number of frames in the sheet.
+    #[must_use]
+    pub fn len(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The grid cell of a frame, counted left to right and then top to bottom.
+    #[must_use]
+    pub fn cell(&self, frame: u32) -> UVec2 {
+        let columns = self.columns.max(1);
+        uvec2(frame % columns, frame / columns)
+    }
+
+    /// The source rectangle of a frame, usable with [`TextureVertex::quad`].
+    #[must_use]
+    pub fn frame_rect(&self, frame: u32) -> Rect {
+        let size = self.region.size / vec2(self.columns as f32, self.rows as f32);
+        Rect {
+            pos: self.region.pos + self.cell(frame).as_vec2() * size,
+            size,
+        }
+    }
+}
+
+/// How a clip continues once it reaches its last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Start again from the first frame.
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, and then forwards again.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+}
+
+/// A single frame of a [`Clip`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The index of the frame in the [`SpriteSheet`].
+    pub index: u32,
+    /// How long the frame is shown for.
+    pub duration: Duration,
+    /// An event that is returned from [`Animation::update`] when the frame is reached.
+    pub event: Option<String>,
+}
+
+/// A named sequence of frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub mode: PlayMode,
+}
+
+impl Clip {
+    /// Creates a clip where every frame has the same duration.
+    #[must_use]
+    pub fn new(frames: impl IntoIterator<Item = u32>, duration: Duration) -> Self {
+        Self {
+            frames: frames
+                .into_iter()
+                .map(|index| Frame {
+                    index,
+                    duration,
+                    event: None,
+                })
+                .collect(),
+            mode: PlayMode::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_mode(self, mode: PlayMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Attaches an event to the `frame`th frame of the clip.
+    #[must_use]
+    pub fn with_event(mut self, frame: usize, event: impl Into<String>) -> Self {
+        self.frames[frame].event = Some(event.into());
+        self
+    }
+
+    /// Changes the duration of the `frame`th frame of the clip.
+    #[must_use]
+    pub fn with_duration(mut self, frame: usize, duration: Duration) -> Self {
+        self.frames[frame].duration = duration;
+        self
+    }
+}
+
+/// Plays [`Clip`]s from a [`SpriteSheet`].
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub sheet: SpriteSheet,
+    pub clips: FxHashMap<String, Clip>,
+    /// A multiplier on the time passed to [`Animation::update`].
+    pub speed: f32,
+    current: Option<String>,
+    frame: usize,
+    elapsed: Duration,
+    reversed: bool,
+    finished: bool,
+}
+
+impl Animation {
+    #[must_use]
+    pub fn new(sheet: SpriteSheet) -> Self {
+        Self {
+            sheet,
+            clips: FxHashMap::default(),
+            speed: 1.0,
+            current: None,
+            frame: 0,
+            elapsed: Duration::ZERO,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_clip(mut self, name: impl Into<String>, clip: Clip) -> Self {
+        self.clips.insert(name.into(), clip);
+        self
+    }
+
+    #[must_use]
+    pub fn with_speed(self, speed: f32) -> Self {
+        Self { speed, ..self }
+    }
+
+    /// Starts playing a clip from its first frame, does nothing if the clip is already playing.
+    ///
+    /// # Panics
+    /// If there is no clip called `name`.
+    pub fn play(&mut self, name: &str) {
+        assert!(self.clips.contains_key(name), "no clip called {name}");
+        if self.current.as_deref() != Some(name) {
+            self.current = Some(name.to_owned());
+            self.restart();
+        }
+    }
+
+    /// Starts the current clip again from its first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = Duration::ZERO;
+        self.reversed = false;
+        self.finished = false;
+    }
+
+    /// The name of the clip being played.
+    #[must_use]
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Whether a [`PlayMode::Once`] clip has reached its end.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The frame of the current clip being shown.
+    #[must_use]
+    pub fn frame(&self) -> Option<&Frame> {
+        self.clip()?.frames.get(self.frame)
+    }
+
+    /// The source rectangle of the frame being shown.
+    #[must_use]
+    pub fn frame_rect(&self) -> Option<Rect> {
+        self.frame().map(|frame| self.sheet.frame_rect(frame.index))
+    }
+
+    fn clip(&self) -> Option<&Clip> {
+        self.clips.get(self.current.as_ref()?)
+    }
+
+    /// Advances the animation and returns the events of every frame that was reached. Frames with
+    /// a zero duration are skipped over, but their events are still returned.
+    pub fn update(&mut self, delta: Duration) -> Vec<String> {
+        let mut events = Vec::new();
+        let Some(clip) = self.current.as_ref().and_then(|name| self.clips.get(name)) else {
+            return events;
+        };
+        if clip.frames.is_empty() || self.finished {
+            return events;
+        }
+
+        self.elapsed += delta.mul_f32(self.speed.max(0.0));
+        // Stops a clip of only zero length frames from looping forever
+        let mut skipped = 0;
+        loop {
+            let duration = clip.frames[self.frame].duration;
+            if duration.is_zero() {
+                skipped += 1;
+                if skipped > clip.frames.len() * 2 {
+                    break;
+                }
+            } else if self.elapsed < duration {
+                break;
+            } else {
+                skipped = 0;
+            }
+            self.elapsed -= duration;
+
+            let last = clip.frames.len() - 1;
+            match clip.mode {
+                PlayMode::Loop => {
+                    self.frame = if self.frame == last {
+                        0
+                    } else {
+                        self.frame + 1
+                    }
+                }
+                PlayMode::Once => {
+                    if self.frame == last {
+                        self.finished = true;
+                        self.elapsed = Duration::ZERO;
+                        break;
+                    }
+                    self.frame += 1;
+                }
+                PlayMode::PingPong => {
+                    if last == 0 {
+                        break;
+                    }
+                    if self.reversed && self.frame == 0 || !self.reversed && self.frame == last {
+                        self.reversed = !self.reversed;
+                    }
+                    if self.reversed {
+                        self.frame -= 1;
+                    } else {
+                        self.frame += 1;
+                    }
+                }
+            }
+            events.extend(clip.frames[self.frame].event.clone());
+        }
+        events
+    }
+
+    /// Changes the vertices of a sprite made with [`Sprite::new_texture`] to show the current
+    /// frame.
+    pub fn apply(&self, sprite: &mut Sprite) {
+        if let Some(rect) = self.frame_rect() {
+            sprite.set_vertices(&TextureVertex::quad(Some(rect)));
+        }
+    }
+}
+
+/// An [`Animation`] bound to a sprite in the [`Renderer`](crate::renderer::Renderer).
+#[derive(Clone, Debug)]
+pub struct AnimatedSprite {
+    pub sprite: SpriteHandle,
+    pub animation: Animation,
+    shown: Option<Rect>,
+}
+
+impl AnimatedSprite {
+    #[must_use]
+    pub fn new(sprite: SpriteHandle, animation: Animation) -> Self {
+        Self {
+            sprite,
+            animation,
+            shown: None,
+        }
+    }
+
+    /// Advances the animation by [`GameData::delta_time`], updating the sprite if the frame
+    /// changed, and returns the events of every frame that was reached.
+    pub fn update(&mut self, data: &mut GameData) -> Vec<String> {
+        let events = self.animation.update(data.delta_time);
+        let rect = self.animation.frame_rect();
+        if rect != self.shown {
+            if let Some(sprite) = data.renderer.get_mut(self.sprite) {
+                self.animation.apply(sprite);
+                self.shown = rect;
+            }
+        }
+        events
+    }
+}
+
+#[test]
+fn animation_test() {
+    let frame = Duration::from_millis(100);
+    let mut animation = Animation::new(SpriteSheet::new(4, 2))
+        .with_clip(
+            "walk",
+            Clip::new(0..3, frame)
+                .with_mode(PlayMode::PingPong)
+                .with_event(2, "step"),
+        )
+        .with_clip("die", Clip::new(4..6, frame).with_mode(PlayMode::Once));
+
+    animation.play("walk");
+    assert_eq!(animation.update(frame * 2), vec!["step".to_owned()]);
+    assert_eq!(animation.update(frame).len(), 0);
+    assert_eq!(animation.frame().unwrap().index, 1);
+    animation.update(frame);
+    assert_eq!(animation.frame().unwrap().index, 0);
+
+    animation.play("die");
+    animation.update(frame * 5);
+    assert!(animation.is_finished());
+    assert_eq!(
+        animation.frame_rect(),
+        Some(Rect {
+            pos: vec2(0.25, 0.5),
+            size: vec2(0.25, 0.5),
+        })
+    );
+
+    animation.clips.insert(
+        "blink".to_owned(),
+        Clip::new(0..3, frame)
+            .with_duration(1, Duration::ZERO)
+            .with_event(1, "closed"),
+    );
+    animation.play("blink");
+    assert_eq!(animation.update(frame), vec!["closed".to_owned()]);
+    assert_eq!(animation.frame().unwrap().index, 2);
+    animation.clips.insert(
+        "empty".to_owned(),
+        Clip::new(0..2, Duration::ZERO).with_mode(PlayMode::PingPong),
+    );
+    animation.play("empty");
+    animation.update(frame);
+
+    assert_eq!(SpriteSheet::new(3, 1).cell(4), uvec2(1, 1));
+    assert!(std::panic::catch_unwind(|| SpriteSheet::new(0, 1)).is_err());
+}
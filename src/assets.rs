@@ -0,0 +1,651 @@
+//! Loading assets by logical path from directories, embedded files and pack files.
+
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt, fs,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{
+    text::TextManager,
+    texture::{
+        Texture, TextureError, TextureHandle, TextureKey, TextureManager, TextureOptions,
+        TextureSource,
+    },
+};
+
+const PACK_MAGIC: &[u8; 4] = b"RPAK";
+const PACK_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// No mount has a file at the path.
+    NotFound(String),
+    /// The path leaves its mount with `..` or isn't valid UTF-8.
+    InvalidPath(String),
+    /// A file or pack couldn't be read.
+    Io(io::Error),
+    /// A pack file is corrupt or from a newer version.
+    InvalidPack(String),
+    Texture(TextureError),
+    Font(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::NotFound(path) => write!(f, "asset not found: {path}"),
+            AssetError::InvalidPath(path) => write!(f, "invalid asset path: {path}"),
+            AssetError::Io(e) => write!(f, "failed to read asset: {e}"),
+            AssetError::InvalidPack(e) => write!(f, "invalid pack file: {e}"),
+            AssetError::Texture(e) => e.fmt(f),
+            AssetError::Font(e) => write!(f, "failed to load font: {e}"),
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::Io(e) => Some(e),
+            AssetError::Texture(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssetError {
+    fn from(e: io::Error) -> Self {
+        AssetError::Io(e)
+    }
+}
+
+impl From<TextureError> for AssetError {
+    fn from(e: TextureError) -> Self {
+        AssetError::Texture(e)
+    }
+}
+
+/// Turns a logical path like `"sprites/chess.png"` into its canonical form, with `/` separators
+/// and no empty or `.` components.
+fn normalize(path: &str) -> Result<String, AssetError> {
+    let mut normalized = String::with_capacity(path.len());
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => return Err(AssetError::InvalidPath(path.to_owned())),
+            component => {
+                if !normalized.is_empty() {
+                    normalized.push('/');
+                }
+                normalized.push_str(component);
+            }
+        }
+    }
+    Ok(normalized)
+}
+
+/// Where the files under a mount point come from.
+#[derive(Debug)]
+pub enum Mount {
+    /// A directory on disk, read every time a file is loaded.
+    Directory(PathBuf),
+    /// Files compiled into the executable, such as with `include_bytes!`.
+    Embedded(FxHashMap<String, &'static [u8]>),
+    /// A pack file made with a [`PackWriter`].
+    Pack(Pack),
+}
+
+impl Mount {
+    /// Embeds files from pairs of paths and contents.
+    #[must_use]
+    pub fn embedded(files: impl IntoIterator<Item = (&'static str, &'static [u8])>) -> Self {
+        Mount::Embedded(
+            files
+                .into_iter()
+                .filter_map(|(path, bytes)| Some((normalize(path).ok()?, bytes)))
+                .collect(),
+        )
+    }
+
+    /// Reads a file, returning `None` if the mount doesn't have it.
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, AssetError> {
+        match self {
+            Mount::Directory(root) => match fs::read(root.join(path)) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Mount::Embedded(files) => Ok(files.get(path).map(|bytes| bytes.to_vec())),
+            Mount::Pack(pack) => pack.read(path),
+        }
+    }
+
+    /// Where to load a texture from, returning `None` if the mount doesn't have it. Files in a
+    /// directory are read by whoever loads the source, such as a background thread.
+    fn texture_source(&self, path: &str) -> Result<Option<TextureSource>, AssetError> {
+        Ok(match self {
+            Mount::Directory(root) => {
+                let path = root.join(path);
+                path.is_file().then(|| TextureSource::path(path))
+            }
+            Mount::Embedded(files) => files.get(path).map(|bytes| TextureSource::memory(*bytes)),
+            Mount::Pack(pack) => pack.read(path)?.map(TextureSource::memory),
+        })
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        match self {
+            Mount::Directory(root) => root.join(path).is_file(),
+            Mount::Embedded(files) => files.contains_key(path),
+            Mount::Pack(pack) => pack.contains(path),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PackData {
+    File(fs::File),
+    Memory(Cow<'static, [u8]>),
+}
+
+/// An archive of many files in one, read with an index so only the requested files are loaded.
+///
+/// The format is the magic `RPAK`, a version, the number of files, then for each file its path,
+/// offset and length, followed by the contents of the files. All integers are little endian.
+#[derive(Debug)]
+pub struct Pack {
+    data: PackData,
+    entries: FxHashMap<String, (u64, u64)>,
+}
+
+impl Pack {
+    /// Opens a pack file, reading its index.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        let entries = Self::read_index(&mut file, len)?;
+        Ok(Self {
+            data: PackData::File(file),
+            entries,
+        })
+    }
+
+    /// Reads a pack from memory, such as one embedded with `include_bytes!`.
+    pub fn from_bytes(bytes: impl Into<Cow<'static, [u8]>>) -> Result<Self, AssetError> {
+        let bytes = bytes.into();
+        let entries = Self::read_index(&mut Cursor::new(&bytes), bytes.len() as u64)?;
+        Ok(Self {
+            data: PackData::Memory(bytes),
+            entries,
+        })
+    }
+
+    fn read_index(
+        reader: &mut impl Read,
+        len: u64,
+    ) -> Result<FxHashMap<String, (u64, u64)>, AssetError> {
+        fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        }
+        fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        }
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != PACK_MAGIC {
+            return Err(AssetError::InvalidPack("not a pack file".to_owned()));
+        }
+        let version = read_u32(reader)?;
+        if version != PACK_VERSION {
+            return Err(AssetError::InvalidPack(format!(
+                "unsupported version {version}"
+            )));
+        }
+
+        let count = read_u32(reader)?;
+        let mut entries = FxHashMap::default();
+        for _ in 0..count {
+            let path_len = read_u32(reader)?;
+            if u64::from(path_len) > len {
+                return Err(AssetError::InvalidPack("path is too long".to_owned()));
+            }
+            let mut path = vec![0; path_len as usize];
+            reader.read_exact(&mut path)?;
+            let path = String::from_utf8(path)
+                .map_err(|_| AssetError::InvalidPack("path isn't UTF-8".to_owned()))?;
+            let offset = read_u64(reader)?;
+            let size = read_u64(reader)?;
+            if offset.checked_add(size).map_or(true, |end| end > len) {
+                return Err(AssetError::InvalidPack(format!("{path} is out of bounds")));
+            }
+            entries.insert(path, (offset, size));
+        }
+        Ok(entries)
+    }
+
+    /// Reads a file, returning `None` if the pack doesn't have it.
+    pub fn read(&self, path: &str) -> Result<Option<Vec<u8>>, AssetError> {
+        let Some(&(offset, size)) = self.entries.get(path) else {
+            return Ok(None);
+        };
+        Ok(Some(match &self.data {
+            PackData::File(file) => {
+                let mut file = file;
+                let mut bytes = vec![0; size as usize];
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut bytes)?;
+                bytes
+            }
+            PackData::Memory(data) => data[offset as usize..(offset + size) as usize].to_vec(),
+        }))
+    }
+
+    #[must_use]
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// The paths of the files in the pack.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+}
+
+/// Builds a [`Pack`] file, such as from the assets directory as part of a release build.
+#[derive(Debug, Default)]
+pub struct PackWriter {
+    files: Vec<(String, Cow<'static, [u8]>)>,
+}
+
+impl PackWriter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing any earlier file with the same path.
+    pub fn add(
+        &mut self,
+        path: &str,
+        bytes: impl Into<Cow<'static, [u8]>>,
+    ) -> Result<(), AssetError> {
+        let path = normalize(path)?;
+        self.files.retain(|(existing, _)| *existing != path);
+        self.files.push((path, bytes.into()));
+        Ok(())
+    }
+
+    /// Adds every file in a directory and its subdirectories, with paths relative to it. Symbolic
+    /// links to files are read, but links to directories aren't followed, so a link cycle can't
+    /// loop forever.
+    pub fn add_directory(&mut self, root: impl AsRef<Path>) -> Result<(), AssetError> {
+        let root = root.as_ref();
+        let mut directories = vec![root.to_owned()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    directories.push(path);
+                    continue;
+                }
+                if file_type.is_symlink() && path.is_dir() {
+                    continue;
+                }
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                let name = relative
+                    .to_str()
+                    .ok_or_else(|| AssetError::InvalidPath(relative.display().to_string()))?;
+                self.add(name, fs::read(&path)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the pack, sorted by path so the output is reproducible.
+    pub fn write(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        self.files.sort_by(|a, b| a.0.cmp(&b.0));
+        let index_len: usize = self.files.iter().map(|(path, _)| 4 + path.len() + 16).sum();
+
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&PACK_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.files.len() as u32).to_le_bytes())?;
+        let mut offset = (12 + index_len) as u64;
+        for (path, bytes) in &self.files {
+            writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+            offset += bytes.len() as u64;
+        }
+        for (_, bytes) in &self.files {
+            writer.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the pack to a file.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// The encoded contents of a sound file, to be decoded and played by an audio library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sound(Arc<[u8]>);
+
+impl Sound {
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[derive(Debug)]
+enum Asset {
+    Texture(Weak<Texture>, TextureOptions),
+    /// The index of the font, and the hash fontdue made of its file so a different font that
+    /// reused the index after it was removed isn't mistaken for it.
+    Font(usize, usize),
+    Sound(Sound),
+}
+
+/// Loads assets by logical paths like `"sprites/chess.png"` from mounted directories, embedded
+/// files and packs, so a game can read loose files during development and ship a single pack.
+#[derive(Debug, Default)]
+pub struct Assets {
+    mounts: Vec<(String, Mount)>,
+    cache: FxHashMap<String, Asset>,
+    /// Increased whenever the mounts change or the cache is cleared, and part of the keys
+    /// textures are cached under in the [`TextureManager`], so a path loaded before the change
+    /// is read again.
+    generation: u64,
+    /// The keys of the textures loaded in this generation.
+    textures: FxHashSet<TextureKey>,
+    /// The keys of textures from earlier generations, which are removed from the
+    /// [`TextureManager`] the next time it's used.
+    stale: Vec<TextureKey>,
+}
+
+impl Assets {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the files of a mount available under `point`, or at the root if it's empty. Later
+    /// mounts take priority, so a directory mounted after a pack can override its files. Loaded
+    /// assets are forgotten, as with [`Assets::clear`].
+    pub fn mount(&mut self, point: &str, mount: Mount) -> Result<(), AssetError> {
+        self.mounts.push((normalize(point)?, mount));
+        self.clear();
+        Ok(())
+    }
+
+    /// Removes every mount at `point`, and forgets loaded assets as with [`Assets::clear`].
+    pub fn unmount(&mut self, point: &str) {
+        if let Ok(point) = normalize(point) {
+            self.mounts.retain(|(existing, _)| *existing != point);
+            self.clear();
+        }
+    }
+
+    /// The mounts that could have a path, by priority, with the path inside each.
+    fn resolve<'a>(&'a self, path: &'a str) -> impl Iterator<Item = (&'a Mount, &'a str)> {
+        self.mounts.iter().rev().filter_map(move |(point, mount)| {
+            if point.is_empty() {
+                return Some((mount, path));
+            }
+            let rest = path.strip_prefix(point.as_str())?.strip_prefix('/')?;
+            Some((mount, rest))
+        })
+    }
+
+    #[must_use]
+    pub fn exists(&self, path: &str) -> bool {
+        normalize(path).is_ok_and(|path| {
+            self.resolve(&path)
+                .any(|(mount, inner)| mount.contains(inner))
+        })
+    }
+
+    /// Reads the contents of a file from the highest priority mount that has it.
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, AssetError> {
+        let path = normalize(path)?;
+        for (mount, inner) in self.resolve(&path) {
+            if let Some(bytes) = mount.read(inner)? {
+                return Ok(bytes);
+            }
+        }
+        Err(AssetError::NotFound(path))
+    }
+
+    /// Finds the highest priority mount with a texture.
+    fn texture_source(&self, path: &str) -> Result<TextureSource, AssetError> {
+        for (mount, inner) in self.resolve(path) {
+            if let Some(source) = mount.texture_source(inner)? {
+                return Ok(source);
+            }
+        }
+        Err(AssetError::NotFound(path.to_owned()))
+    }
+
+    /// The key a texture is cached under in the [`TextureManager`].
+    fn texture_key(&self, path: &str) -> TextureKey {
+        TextureKey::Name(format!("{path}@{}", self.generation).into())
+    }
+
+    /// Removes the textures of earlier generations from the manager.
+    fn remove_stale(&mut self, texture_manager: &mut TextureManager) {
+        for key in self.stale.drain(..) {
+            texture_manager.remove(&key);
+        }
+    }
+
+    /// Loads a texture with the manager's options, or returns the already loaded one.
+    pub fn texture(
+        &mut self,
+        texture_manager: &mut TextureManager,
+        path: &str,
+    ) -> Result<Arc<Texture>, AssetError> {
+        let options = texture_manager.options;
+        self.texture_with_options(texture_manager, path, options)
+    }
+
+    /// Loads a texture with its own options, or returns the already loaded one.
+    pub fn texture_with_options(
+        &mut self,
+        texture_manager: &mut TextureManager,
+        path: &str,
+        options: TextureOptions,
+    ) -> Result<Arc<Texture>, AssetError> {
+        self.remove_stale(texture_manager);
+        let path = normalize(path)?;
+        if let Some(Asset::Texture(texture, cached_options)) = self.cache.get(&path) {
+            if let Some(texture) = texture.upgrade().filter(|_| *cached_options == options) {
+                return Ok(texture);
+            }
+        }
+        let key = self.texture_key(&path);
+        let texture = match texture_manager.get(key.clone(), options) {
+            Some(texture) => texture,
+            None => {
+                let source = self.texture_source(&path)?;
+                texture_manager.try_load_with_options(key.clone(), &source, options)?
+            }
+        };
+        self.textures.insert(key);
+        self.cache
+            .insert(path, Asset::Texture(Arc::downgrade(&texture), options));
+        Ok(texture)
+    }
+
+    /// Starts loading a texture in the background, see [`TextureManager::load_async`], or
+    /// returns the already loaded one. Files in directories are read in the background too, but
+    /// files in packs are read now.
+    pub fn texture_async(
+        &mut self,
+        texture_manager: &mut TextureManager,
+        path: &str,
+    ) -> Result<TextureHandle, AssetError> {
+        self.remove_stale(texture_manager);
+        let path = normalize(path)?;
+        let key = self.texture_key(&path);
+        if let Some(texture) = texture_manager.get(key.clone(), texture_manager.options) {
+            return Ok(TextureHandle::loaded(texture));
+        }
+        let source = self.texture_source(&path)?;
+        self.textures.insert(key.clone());
+        Ok(texture_manager.load_async_with_key(key, source))
+    }
+
+    /// Loads a font and returns its index in the text manager, or the index of the already
+    /// loaded one.
+    pub fn font(
+        &mut self,
+        text_manager: &mut TextManager,
+        path: &str,
+    ) -> Result<usize, AssetError> {
+        let path = normalize(path)?;
+        if let Some(Asset::Font(index, hash)) = self.cache.get(&path) {
+            if text_manager
+                .fonts
+                .get(*index)
+                .is_some_and(|font| font.file_hash() == *hash)
+            {
+                return Ok(*index);
+            }
+        }
+        let index = text_manager
+            .load_bytes(&self.read(&path)?)
+            .map_err(|e| AssetError::Font(e.to_string()))?;
+        let hash = text_manager.fonts[index].file_hash();
+        self.cache.insert(path, Asset::Font(index, hash));
+        Ok(index)
+    }
+
+    /// Loads the contents of a sound file, or returns the already loaded one.
+    pub fn sound(&mut self, path: &str) -> Result<Sound, AssetError> {
+        let path = normalize(path)?;
+        if let Some(Asset::Sound(sound)) = self.cache.get(&path) {
+            return Ok(sound.clone());
+        }
+        let sound = Sound(self.read(&path)?.into());
+        self.cache.insert(path, Asset::Sound(sound.clone()));
+        Ok(sound)
+    }
+
+    /// Forgets loaded assets, so they're read again from the mounts next time. Textures are
+    /// removed from the [`TextureManager`] the next time one is loaded.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.generation += 1;
+        self.stale.extend(self.textures.drain());
+    }
+}
+
+#[test]
+fn pack_test() {
+    let mut writer = PackWriter::new();
+    writer.add("sprites/b.png", b"bbb".as_slice()).unwrap();
+    writer.add("./a.txt", b"a".as_slice()).unwrap();
+    writer.add("sprites//b.png", b"b".as_slice()).unwrap();
+    assert!(matches!(
+        writer.add("../c", Vec::new()),
+        Err(AssetError::InvalidPath(_))
+    ));
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).unwrap();
+
+    let pack = Pack::from_bytes(bytes.clone()).unwrap();
+    assert_eq!(pack.read("a.txt").unwrap(), Some(b"a".to_vec()));
+    assert_eq!(pack.read("sprites/b.png").unwrap(), Some(b"b".to_vec()));
+    assert_eq!(pack.read("c").unwrap(), None);
+    assert!(matches!(
+        Pack::from_bytes(bytes[..bytes.len() - 1].to_vec()),
+        Err(AssetError::InvalidPack(_))
+    ));
+
+    let mut assets = Assets::new();
+    assets.mount("", Mount::Pack(pack)).unwrap();
+    assets
+        .mount(
+            "sprites",
+            Mount::embedded([("b.png", b"override".as_slice())]),
+        )
+        .unwrap();
+    assert_eq!(assets.read("/sprites/b.png").unwrap(), b"override");
+    assert_eq!(assets.read("a.txt").unwrap(), b"a");
+    assert!(assets.exists("sprites/b.png"));
+    assert!(!assets.exists("b.png"));
+    assert!(matches!(assets.read("b.png"), Err(AssetError::NotFound(_))));
+    assert_eq!(assets.sound("a.txt").unwrap().bytes(), b"a");
+}
+
+#[test]
+fn mount_test() {
+    let root = std::env::temp_dir().join(format!("assets_mount_test_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("a.png"), b"directory").unwrap();
+
+    let mut assets = Assets::new();
+    assets
+        .mount(
+            "",
+            Mount::embedded([("sprites/a.png", b"embedded".as_slice())]),
+        )
+        .unwrap();
+    let embedded = assets.texture_key("sprites/a.png");
+    assert!(matches!(
+        assets.texture_source("sprites/a.png").unwrap().key(),
+        TextureKey::Static(_, 8)
+    ));
+
+    // A texture loaded before an override is mounted is cached under a different key
+    assets
+        .mount("sprites", Mount::Directory(root.clone()))
+        .unwrap();
+    let directory = assets.texture_key("sprites/a.png");
+    assert_ne!(directory, embedded);
+    assert_eq!(
+        assets.texture_source("sprites/a.png").unwrap().key(),
+        &TextureKey::Path(root.join("a.png"))
+    );
+    assert_eq!(assets.read("sprites/a.png").unwrap(), b"directory");
+    assert!(matches!(
+        assets.texture_source("sprites/b.png"),
+        Err(AssetError::NotFound(_))
+    ));
+
+    // Textures of the old generation are removed from the manager once it's used again
+    assets.textures.insert(directory.clone());
+    assets.unmount("sprites");
+    assert_eq!(assets.stale.len(), 1);
+    assets.remove_stale(&mut TextureManager::default());
+    assert!(assets.stale.is_empty() && assets.textures.is_empty());
+    assert_ne!(assets.texture_key("sprites/a.png"), directory);
+    assert_eq!(assets.read("sprites/a.png").unwrap(), b"embedded");
+
+    // A link back to the root would loop forever if it was followed
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+    let mut writer = PackWriter::new();
+    writer.add_directory(&root).unwrap();
+    assert_eq!(writer.files.len(), 1);
+    fs::remove_dir_all(root).unwrap();
+}
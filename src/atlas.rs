@@ -0,0 +1,378 @@
+//! Packing many images into a few large textures.
+
+use std::sync::Arc;
+
+use fxhash::FxHashMap;
+use glam::{uvec2, UVec2};
+use image::RgbaImage;
+
+use crate::{
+    geometry::Rect,
+    texture::{
+        DirtyRegion, MipmapGenerator, Texture, TextureError, TextureFormat, TextureKey,
+        TextureOptions,
+    },
+};
+
+/// Places rectangles in rows, starting a new row below the last when one doesn't fit.
+#[derive(Clone, Debug)]
+struct ShelfPacker {
+    size: UVec2,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    width: u32,
+}
+
+impl ShelfPacker {
+    fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+        }
+    }
+
+    /// Finds space for a rectangle, returning its top left corner.
+    fn pack(&mut self, size: UVec2) -> Option<UVec2> {
+        if size.x > self.size.x || size.y > self.size.y {
+            return None;
+        }
+        // Use the shortest shelf that fits to waste the least space
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= size.y && self.size.x - shelf.width >= size.x)
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = shelf {
+            let pos = uvec2(shelf.width, shelf.y);
+            shelf.width += size.x;
+            return Some(pos);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if self.size.y - y < size.y {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height: size.y,
+            width: size.x,
+        });
+        Some(uvec2(0, y))
+    }
+}
+
+#[derive(Debug)]
+struct Page {
+    image: RgbaImage,
+    packer: ShelfPacker,
+    /// Slots of images that were replaced, which are reused before packing new space.
+    free: Vec<Slot>,
+    texture: Option<Arc<Texture>>,
+    dirty: DirtyRegion,
+}
+
+/// The space an image and its border were packed into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Slot {
+    pos: UVec2,
+    size: UVec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Region {
+    page: usize,
+    slot: Slot,
+    /// The area of the image in texture coordinates.
+    rect: Rect,
+}
+
+/// Packs images into large textures, so many sprites can share one texture.
+#[derive(Debug)]
+pub struct TextureAtlas {
+    page_size: u32,
+    /// The number of empty pixels around each image.
+    pub padding: u32,
+    /// The number of times the edge pixels of each image are repeated outwards, which stops
+    /// neighbouring images bleeding in when filtering.
+    pub extrusion: u32,
+    /// The options of the page textures. Only 8-bit formats can be used, and mipmaps are
+    /// regenerated every time images are uploaded.
+    pub options: TextureOptions,
+    pages: Vec<Page>,
+    regions: FxHashMap<TextureKey, Region>,
+    mipmaps: Option<MipmapGenerator>,
+}
+
+impl Default for TextureAtlas {
+    fn default() -> Self {
+        Self::new(2048)
+    }
+}
+
+impl TextureAtlas {
+    /// Creates an empty atlas whose pages are `page_size` pixels wide and tall.
+    #[must_use]
+    pub fn new(page_size: u32) -> Self {
+        Self {
+            page_size,
+            padding: 1,
+            extrusion: 1,
+            options: TextureOptions::default(),
+            pages: Vec::new(),
+            regions: FxHashMap::default(),
+            mipmaps: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_padding(self, padding: u32) -> Self {
+        Self { padding, ..self }
+    }
+
+    #[must_use]
+    pub fn with_extrusion(self, extrusion: u32) -> Self {
+        Self { extrusion, ..self }
+    }
+
+    #[must_use]
+    pub fn with_options(self, options: TextureOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Copies an image into the atlas, replacing the region if the key was already used. The
+    /// space of a replaced image is reused, so an image of the same size is replaced in place.
+    /// The image is uploaded the next time a region on its page is requested, without reuploading
+    /// the rest of the page.
+    ///
+    /// # Errors
+    /// If the image doesn't fit on a page, or the format of the options isn't
+    /// [`TextureFormat::Srgb`] or [`TextureFormat::Linear`].
+    pub fn insert(
+        &mut self,
+        key: impl Into<TextureKey>,
+        image: &RgbaImage,
+    ) -> Result<(), TextureError> {
+        if !matches!(
+            self.options.format,
+            TextureFormat::Srgb | TextureFormat::Linear
+        ) {
+            return Err(TextureError::UnsupportedFormat(self.options.format));
+        }
+        let key = key.into();
+        if let Some(old) = self.regions.remove(&key) {
+            self.pages[old.page].free.push(old.slot);
+        }
+
+        let border = self.padding + self.extrusion;
+        let size = uvec2(image.width(), image.height()) + border * 2;
+        let (index, slot) = self.allocate(size).ok_or(TextureError::TooLarge {
+            size: uvec2(image.width(), image.height()),
+            max: self.page_size.saturating_sub(border * 2),
+        })?;
+
+        let page = &mut self.pages[index];
+        // Clear whatever was in a reused slot so it can't bleed into the padding
+        for y in slot.pos.y..slot.pos.y + slot.size.y {
+            for x in slot.pos.x..slot.pos.x + slot.size.x {
+                page.image.put_pixel(x, y, image::Rgba([0; 4]));
+            }
+        }
+        page.dirty.add(slot.pos, slot.size);
+
+        let pos = slot.pos + self.padding;
+        let extruded = if image.width() == 0 || image.height() == 0 {
+            UVec2::ZERO
+        } else {
+            uvec2(image.width(), image.height()) + self.extrusion * 2
+        };
+        for y in 0..extruded.y {
+            for x in 0..extruded.x {
+                let source_x = x.saturating_sub(self.extrusion).min(image.width() - 1);
+                let source_y = y.saturating_sub(self.extrusion).min(image.height() - 1);
+                page.image
+                    .put_pixel(pos.x + x, pos.y + y, *image.get_pixel(source_x, source_y));
+            }
+        }
+
+        let pos = pos + self.extrusion;
+        self.regions.insert(
+            key,
+            Region {
+                page: index,
+                slot,
+                rect: Rect {
+                    pos: pos.as_vec2() / self.page_size as f32,
+                    size: uvec2(image.width(), image.height()).as_vec2() / self.page_size as f32,
+                },
+            },
+        );
+        Ok(())
+    }
+
+    /// Finds space for an image and its border, preferring the smallest freed slot that fits,
+    /// then new space on an existing page, then a new page.
+    fn allocate(&mut self, size: UVec2) -> Option<(usize, Slot)> {
+        let free = self
+            .pages
+            .iter()
+            .enumerate()
+            .flat_map(|(index, page)| {
+                page.free
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, slot)| (index, i, slot))
+            })
+            .filter(|(_, _, slot)| slot.size.x >= size.x && slot.size.y >= size.y)
+            .min_by_key(|(_, _, slot)| slot.size.x * slot.size.y)
+            .map(|(index, i, _)| (index, i));
+        if let Some((index, i)) = free {
+            return Some((index, self.pages[index].free.swap_remove(i)));
+        }
+
+        let packed = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, page)| Some((index, page.packer.pack(size)?)));
+        if let Some((index, pos)) = packed {
+            return Some((index, Slot { pos, size }));
+        }
+
+        let mut packer = ShelfPacker::new(UVec2::splat(self.page_size));
+        let pos = packer.pack(size)?;
+        self.pages.push(Page {
+            image: RgbaImage::new(self.page_size, self.page_size),
+            packer,
+            free: Vec::new(),
+            texture: None,
+            dirty: DirtyRegion::default(),
+        });
+        Some((self.pages.len() - 1, Slot { pos, size }))
+    }
+
+    /// The texture of the page an image was packed into and the area of it, in the same units as
+    /// the source rectangle of [`Sprite::new_texture`](crate::sprite::Sprite::new_texture), or
+    /// `None` if there's no image with the key.
+    pub fn get(&mut self, key: &TextureKey) -> Result<Option<(Arc<Texture>, Rect)>, TextureError> {
+        let Some(region) = self.regions.get(key).copied() else {
+            return Ok(None);
+        };
+        Ok(self
+            .page_texture(region.page)?
+            .map(|texture| (texture, region.rect)))
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &TextureKey) -> bool {
+        self.regions.contains_key(key)
+    }
+
+    /// The number of images in the atlas.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    #[must_use]
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The pixels of a page, such as for saving it to check how well images are packed.
+    #[must_use]
+    pub fn page_image(&self, index: usize) -> Option<&RgbaImage> {
+        Some(&self.pages.get(index)?.image)
+    }
+
+    /// The texture of a page, uploading the images added since the last upload first, or `None`
+    /// if there's no page with the index.
+    pub fn page_texture(&mut self, index: usize) -> Result<Option<Arc<Texture>>, TextureError> {
+        let Some(page) = self.pages.get_mut(index) else {
+            return Ok(None);
+        };
+        let uploaded = match &page.texture {
+            Some(texture) => {
+                let dirty = page.dirty.take();
+                if let Some((pos, size)) = dirty {
+                    texture.write_subimage(pos, &page.image, pos, size);
+                }
+                dirty.is_some()
+            }
+            None => {
+                let texture = Texture::from_image(&page.image, self.options)?;
+                page.texture = Some(Arc::new(Texture::new(texture)));
+                page.dirty.take();
+                true
+            }
+        };
+        if uploaded && self.options.mipmaps {
+            if let Some(texture) = &page.texture {
+                self.mipmaps
+                    .get_or_insert_with(MipmapGenerator::new)
+                    .generate(&texture.inner().texture);
+            }
+        }
+        Ok(page.texture.clone())
+    }
+}
+
+#[test]
+fn atlas_packing_test() {
+    let mut packer = ShelfPacker::new(UVec2::splat(8));
+    assert_eq!(packer.pack(uvec2(4, 4)), Some(uvec2(0, 0)));
+    assert_eq!(packer.pack(uvec2(4, 2)), Some(uvec2(4, 0)));
+    assert_eq!(packer.pack(uvec2(2, 2)), Some(uvec2(0, 4)));
+    assert_eq!(packer.pack(uvec2(6, 2)), Some(uvec2(2, 4)));
+    assert_eq!(packer.pack(uvec2(8, 3)), None);
+    assert_eq!(packer.pack(uvec2(8, 2)), Some(uvec2(0, 6)));
+
+    let mut atlas = TextureAtlas::new(8).with_padding(1).with_extrusion(1);
+    let image = RgbaImage::from_fn(2, 2, |x, y| image::Rgba([x as u8, y as u8, 1, 255]));
+    atlas.insert("a", &image).unwrap();
+    assert_eq!(
+        atlas.regions[&TextureKey::from("a")].rect,
+        Rect {
+            pos: glam::Vec2::splat(2.0 / 8.0),
+            size: glam::Vec2::splat(2.0 / 8.0),
+        }
+    );
+    let page = atlas.page_image(0).unwrap();
+    assert_eq!(page.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    assert_eq!(page.get_pixel(1, 1).0, [0, 0, 1, 255]);
+    assert_eq!(page.get_pixel(4, 4).0, [1, 1, 1, 255]);
+    assert_eq!(page.get_pixel(5, 2).0, [0, 0, 0, 0]);
+
+    // Replacing an image reuses its space
+    let before = atlas.regions[&TextureKey::from("a")];
+    atlas.insert("a", &RgbaImage::new(2, 2)).unwrap();
+    assert_eq!(atlas.regions[&TextureKey::from("a")], before);
+    assert_eq!(atlas.page_image(0).unwrap().get_pixel(4, 4).0, [0; 4]);
+    atlas.insert("a", &RgbaImage::new(2, 1)).unwrap();
+    assert_eq!(atlas.page_count(), 1);
+    assert!(atlas.pages[0].free.is_empty());
+
+    atlas.insert("b", &image).unwrap();
+    assert_eq!(atlas.page_count(), 2);
+    assert!(matches!(
+        atlas.insert("d", &RgbaImage::new(5, 5)),
+        Err(TextureError::TooLarge { .. })
+    ));
+    atlas.options.format = TextureFormat::R8;
+    assert!(matches!(
+        atlas.insert("e", &image),
+        Err(TextureError::UnsupportedFormat(TextureFormat::R8))
+    ));
+}
@@ -1,12 +1,220 @@
-use glam::{Mat4, Vec2, Vec3};
+use std::time::Duration;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+use dirtytype::Dirty;
+use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+
+use crate::sprite::Rect;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Camera {
     pub translation: Vec2,
+    /// How many times larger the world appears, where `1.0` is unzoomed.
+    pub zoom: f32,
+    /// The counter-clockwise rotation of the camera in radians.
+    pub rotation: f32,
+}
+
+impl Camera {
+    #[must_use]
+    pub fn with_translation(self, translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_zoom(self, zoom: f32) -> Self {
+        Self { zoom, ..self }
+    }
+
+    #[must_use]
+    pub fn with_rotation(self, rotation: f32) -> Self {
+        Self { rotation, ..self }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
 }
 
 impl From<&Camera> for Mat4 {
     fn from(value: &Camera) -> Self {
-        Self::look_to_rh(value.translation.extend(0.0), Vec3::NEG_Z, Vec3::Y)
+        Self::from_scale(vec3(value.zoom, value.zoom, 1.0))
+            * Self::from_rotation_z(-value.rotation)
+            * Self::look_to_rh(value.translation.extend(0.0), Vec3::NEG_Z, Vec3::Y)
+    }
+}
+
+/// Moves a [`Camera`] to follow a target, stay inside the world, and shake.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraController {
+    /// The position the camera should follow.
+    pub target: Option<Vec2>,
+    /// Half the size of the area around the centre of the camera the target can move in without
+    /// the camera following it.
+    pub dead_zone: Vec2,
+    /// How quickly the camera catches up with the target, where higher is faster and
+    /// [`f32::INFINITY`] follows exactly.
+    pub damping: f32,
+    /// The area of the world the camera's view should stay inside.
+    pub bounds: Option<Rect>,
+    /// The rotation of the camera when it isn't shaking.
+    pub rotation: f32,
+    /// How much the camera is shaking, between 0 and 1.
+    pub trauma: f32,
+    /// How much trauma is lost per second.
+    pub trauma_decay: f32,
+    /// The furthest the camera can move from its position when shaking.
+    pub max_shake_offset: Vec2,
+    /// The furthest the camera can rotate from its rotation when shaking, in radians.
+    pub max_shake_angle: f32,
+    position: Vec2,
+    time: f32,
+}
+
+impl CameraController {
+    #[must_use]
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            target: None,
+            dead_zone: Vec2::ZERO,
+            damping: 5.0,
+            bounds: None,
+            rotation: 0.0,
+            trauma: 0.0,
+            trauma_decay: 1.0,
+            max_shake_offset: Vec2::splat(0.5),
+            max_shake_angle: 0.1,
+            position,
+            time: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub fn with_target(self, target: Vec2) -> Self {
+        Self {
+            target: Some(target),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_dead_zone(self, dead_zone: Vec2) -> Self {
+        Self { dead_zone, ..self }
+    }
+
+    #[must_use]
+    pub fn with_damping(self, damping: f32) -> Self {
+        Self { damping, ..self }
     }
+
+    #[must_use]
+    pub fn with_bounds(self, bounds: Rect) -> Self {
+        Self {
+            bounds: Some(bounds),
+            ..self
+        }
+    }
+
+    /// The position of the camera without any shaking.
+    #[must_use]
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Moves the camera immediately, ignoring damping.
+    pub fn teleport(&mut self, position: Vec2) {
+        self.position = position;
+    }
+
+    /// Makes the camera shake more, trauma is capped at 1.
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    /// Moves the camera, `visible_size` is the size of the unzoomed view, such as from
+    /// [`Projection::visible_size`](crate::renderer::Projection::visible_size). The camera is only
+    /// marked as dirty if it actually changed.
+    pub fn update(&mut self, camera: &mut Dirty<Camera>, visible_size: Vec2, delta: Duration) {
+        let delta = delta.as_secs_f32();
+        self.time += delta;
+
+        if let Some(target) = self.target {
+            let offset = target - self.position;
+            let desired = self.position + offset - offset.clamp(-self.dead_zone, self.dead_zone);
+            let amount = if self.damping.is_infinite() {
+                1.0
+            } else {
+                1.0 - (-self.damping * delta).exp()
+            };
+            self.position = self.position.lerp(desired, amount.clamp(0.0, 1.0));
+        }
+
+        if let Some(bounds) = self.bounds {
+            let half = visible_size / camera.zoom / 2.0;
+            let min = bounds.pos + half;
+            let max = bounds.pos + bounds.size - half;
+            let centre = bounds.pos + bounds.size / 2.0;
+            self.position = vec2(
+                clamp_or_centre(self.position.x, min.x, max.x, centre.x),
+                clamp_or_centre(self.position.y, min.y, max.y, centre.y),
+            );
+        }
+
+        let shake = self.trauma * self.trauma;
+        let offset =
+            self.max_shake_offset * shake * vec2(noise(self.time, 0.0), noise(self.time, 10.0));
+        let angle = self.max_shake_angle * shake * noise(self.time, 20.0);
+        self.trauma = (self.trauma - self.trauma_decay * delta).max(0.0);
+
+        let translation = self.position + offset;
+        let rotation = self.rotation + angle;
+        if camera.translation != translation || camera.rotation != rotation {
+            camera.translation = translation;
+            camera.rotation = rotation;
+        }
+    }
+}
+
+fn clamp_or_centre(value: f32, min: f32, max: f32, centre: f32) -> f32 {
+    if min > max {
+        centre
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+/// Smooth noise between -1 and 1.
+fn noise(time: f32, seed: f32) -> f32 {
+    ((time * 23.0 + seed).sin() + (time * 37.0 + seed * 1.7).sin() * 0.5) / 1.5
+}
+
+#[test]
+fn camera_controller_test() {
+    let mut camera = Dirty::new(Camera::default());
+    let mut controller = CameraController::new(Vec2::ZERO)
+        .with_target(vec2(10.0, 0.5))
+        .with_dead_zone(Vec2::ONE)
+        .with_damping(f32::INFINITY);
+    controller.update(&mut camera, Vec2::splat(2.0), Duration::from_secs(1));
+    assert_eq!(camera.translation, vec2(9.0, 0.0));
+    assert!(camera.dirty);
+
+    camera.dirty = false;
+    controller.update(&mut camera, Vec2::splat(2.0), Duration::from_secs(1));
+    assert!(!camera.dirty);
+
+    controller.bounds = Some(Rect {
+        pos: Vec2::ZERO,
+        size: vec2(8.0, 1.0),
+    });
+    controller.update(&mut camera, Vec2::splat(2.0), Duration::from_secs(1));
+    assert_eq!(camera.translation, vec2(7.0, 0.5));
 }
@@ -0,0 +1,459 @@
+//! Collision detection between 2D shapes.
+
+use std::hash::Hash;
+
+use fxhash::{FxHashMap, FxHashSet};
+use glam::{ivec2, IVec2, Vec2};
+
+use crate::{
+    geometry::Rect,
+    transform::{Transform, Transform2D},
+};
+
+/// The shape of an object in its local space, before its [`Transform`] is applied.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Collider {
+    /// A rectangle of this size centred on the origin, matching an unscaled sprite quad when
+    /// [`Vec2::ONE`].
+    Box(Vec2),
+    /// A circle of this radius, which is scaled by the larger of the transform's scales.
+    Circle(f32),
+    /// A convex polygon with vertices in counter-clockwise order.
+    Polygon(Vec<Vec2>),
+}
+
+impl Collider {
+    /// The collider matching a sprite made with [`Sprite::new_color`] or
+    /// [`Sprite::new_texture`](crate::sprite::Sprite::new_texture).
+    ///
+    /// [`Sprite::new_color`]: crate::sprite::Sprite::new_color
+    #[must_use]
+    pub fn sprite() -> Self {
+        Self::Box(Vec2::ONE)
+    }
+
+    /// Places the collider in the world.
+    #[must_use]
+    pub fn to_shape(&self, transform: &Transform) -> Shape {
+        let transform = Transform2D::from(*transform);
+        match self {
+            Collider::Box(size) => Shape::Polygon(
+                [
+                    Vec2::new(-0.5, -0.5),
+                    Vec2::new(0.5, -0.5),
+                    Vec2::new(0.5, 0.5),
+                    Vec2::new(-0.5, 0.5),
+                ]
+                .map(|corner| transform.transform_point(corner * *size))
+                .to_vec(),
+            ),
+            Collider::Circle(radius) => Shape::Circle {
+                center: transform.position,
+                radius: radius * transform.scale.abs().max_element(),
+            },
+            Collider::Polygon(points) => Shape::Polygon(
+                points
+                    .iter()
+                    .map(|point| transform.transform_point(*point))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A collider placed in the world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// A convex polygon, the winding order doesn't matter.
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    /// The smallest unrotated rectangle containing the shape.
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Shape::Circle { center, radius } => {
+                Rect::from_center_size(*center, Vec2::splat(radius * 2.0))
+            }
+            Shape::Polygon(points) => {
+                let min = points.iter().copied().reduce(Vec2::min).unwrap_or_default();
+                let max = points.iter().copied().reduce(Vec2::max).unwrap_or_default();
+                Rect::from_corners(min, max)
+            }
+        }
+    }
+
+    /// Checks if two shapes overlap, returning how to push `self` out of `other`.
+    #[must_use]
+    pub fn collide(&self, other: &Self) -> Option<Contact> {
+        match (self, other) {
+            (
+                Shape::Circle {
+                    center: a,
+                    radius: a_radius,
+                },
+                Shape::Circle {
+                    center: b,
+                    radius: b_radius,
+                },
+            ) => {
+                let offset = *a - *b;
+                let distance = offset.length();
+                let depth = a_radius + b_radius - distance;
+                (depth > 0.0).then(|| Contact {
+                    normal: offset.try_normalize().unwrap_or(Vec2::Y),
+                    depth,
+                })
+            }
+            (Shape::Circle { center, radius }, Shape::Polygon(points)) => {
+                circle_polygon(*center, *radius, points)
+            }
+            (Shape::Polygon(points), Shape::Circle { center, radius }) => {
+                circle_polygon(*center, *radius, points).map(Contact::flip)
+            }
+            (Shape::Polygon(a), Shape::Polygon(b)) => polygon_polygon(a, b),
+        }
+    }
+}
+
+/// How two overlapping shapes touch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// The direction to move the first shape to separate them.
+    pub normal: Vec2,
+    /// How far the first shape needs to move along the normal to separate them.
+    pub depth: f32,
+}
+
+impl Contact {
+    /// The contact from the point of view of the other shape.
+    #[must_use]
+    pub fn flip(self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+/// Checks if two colliders overlap, returning how to push `a` out of `b`.
+#[must_use]
+pub fn collide(
+    a: &Collider,
+    a_transform: &Transform,
+    b: &Collider,
+    b_transform: &Transform,
+) -> Option<Contact> {
+    a.to_shape(a_transform).collide(&b.to_shape(b_transform))
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points
+        .iter()
+        .map(|point| point.dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+fn center(points: &[Vec2]) -> Vec2 {
+    points.iter().sum::<Vec2>() / points.len().max(1) as f32
+}
+
+fn edge_normals(points: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .filter_map(|(a, b)| (*b - *a).perp().try_normalize())
+}
+
+/// Separating axis test, the contact normal points from `b` to `a`.
+fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+    let mut best: Option<Contact> = None;
+    for axis in edge_normals(a).chain(edge_normals(b)) {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        let depth = a_max.min(b_max) - a_min.max(b_min);
+        if depth <= 0.0 {
+            return None;
+        }
+        if best.map_or(true, |best| depth < best.depth) {
+            best = Some(Contact {
+                normal: axis,
+                depth,
+            });
+        }
+    }
+
+    best.map(|contact| {
+        if (center(a) - center(b)).dot(contact.normal) < 0.0 {
+            contact.flip()
+        } else {
+            contact
+        }
+    })
+}
+
+/// Separating axis test using the polygon's edges and the axis to the closest vertex, the
+/// contact normal points from the polygon to the circle.
+fn circle_polygon(center: Vec2, radius: f32, points: &[Vec2]) -> Option<Contact> {
+    let closest = points.iter().copied().min_by(|a, b| {
+        a.distance_squared(center)
+            .total_cmp(&b.distance_squared(center))
+    })?;
+    let mut best: Option<Contact> = None;
+    for axis in edge_normals(points).chain((center - closest).try_normalize()) {
+        let (min, max) = project(points, axis);
+        let projected = center.dot(axis);
+        let depth = max.min(projected + radius) - min.max(projected - radius);
+        if depth <= 0.0 {
+            return None;
+        }
+        if best.map_or(true, |best| depth < best.depth) {
+            best = Some(Contact {
+                normal: axis,
+                depth,
+            });
+        }
+    }
+
+    best.map(|contact| {
+        if (center - self::center(points)).dot(contact.normal) < 0.0 {
+            contact.flip()
+        } else {
+            contact
+        }
+    })
+}
+
+/// A broadphase that buckets objects into a uniform grid so only nearby objects are tested
+/// against each other.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: FxHashMap<IVec2, Vec<T>>,
+    /// Objects that cover too many cells, or have non-finite bounds, which are tested against
+    /// everything instead.
+    large: Vec<T>,
+    bounds: FxHashMap<T, Rect>,
+}
+
+impl<T: Copy + Eq + Hash> SpatialHash<T> {
+    /// The most cells an object can be bucketed into before it's treated as large.
+    const MAX_CELLS: f32 = 1024.0;
+
+    /// Creates an empty grid, `cell_size` should be around the size of a typical object.
+    ///
+    /// # Panics
+    /// If `cell_size` isn't positive and finite.
+    #[must_use]
+    pub fn new(cell_size: f32) -> Self {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "cell size must be positive, not {cell_size}"
+        );
+        Self {
+            cell_size,
+            cells: FxHashMap::default(),
+            large: Vec::new(),
+            bounds: FxHashMap::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// The cells a rectangle covers, or `None` if there are too many to visit.
+    fn cells(&self, rect: &Rect) -> Option<impl Iterator<Item = IVec2>> {
+        let min = (rect.min() / self.cell_size).floor();
+        let max = (rect.max() / self.cell_size).floor();
+        let count = (max - min + 1.0).max(Vec2::ZERO);
+        if !min.is_finite() || !max.is_finite() || count.x * count.y > Self::MAX_CELLS {
+            return None;
+        }
+        let (min, max) = (min.as_ivec2(), max.as_ivec2());
+        Some((min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| ivec2(x, y))))
+    }
+
+    /// Adds an object, replacing it if it was already added.
+    pub fn insert(&mut self, id: T, bounds: Rect) {
+        self.remove(id);
+        match self.cells(&bounds) {
+            Some(cells) => {
+                for cell in cells.collect::<Vec<_>>() {
+                    self.cells.entry(cell).or_default().push(id);
+                }
+            }
+            None => self.large.push(id),
+        }
+        self.bounds.insert(id, bounds);
+    }
+
+    pub fn remove(&mut self, id: T) {
+        let Some(bounds) = self.bounds.remove(&id) else {
+            return;
+        };
+        let Some(cells) = self.cells(&bounds) else {
+            self.large.retain(|other| *other != id);
+            return;
+        };
+        for cell in cells.collect::<Vec<_>>() {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.large.clear();
+        self.bounds.clear();
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    /// Finds every object whose bounds overlap `rect`.
+    #[must_use]
+    pub fn query(&self, rect: &Rect) -> Vec<T> {
+        let Some(cells) = self.cells(rect) else {
+            // Checking every object is quicker than visiting that many cells
+            return self
+                .bounds
+                .iter()
+                .filter(|(_, bounds)| bounds.overlaps(rect))
+                .map(|(id, _)| *id)
+                .collect();
+        };
+        let mut found = FxHashSet::default();
+        cells
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .chain(&self.large)
+            .filter(|id| self.bounds[*id].overlaps(rect) && found.insert(**id))
+            .copied()
+            .collect()
+    }
+
+    /// Finds every pair of objects whose bounds overlap, each pair is only returned once.
+    #[must_use]
+    pub fn pairs(&self) -> Vec<(T, T)> {
+        let mut found = FxHashSet::default();
+        let mut pairs = Vec::new();
+        for ids in self.cells.values() {
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    if self.bounds[a].overlaps(&self.bounds[b])
+                        && !found.contains(&(*b, *a))
+                        && found.insert((*a, *b))
+                    {
+                        pairs.push((*a, *b));
+                    }
+                }
+            }
+        }
+        for a in &self.large {
+            for b in self.bounds.keys().filter(|b| *b != a) {
+                if self.bounds[a].overlaps(&self.bounds[b])
+                    && !found.contains(&(*b, *a))
+                    && found.insert((*a, *b))
+                {
+                    pairs.push((*a, *b));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+#[test]
+fn collision_test() {
+    let square = Collider::sprite();
+    let contact = collide(
+        &square,
+        &Transform::translation(glam::vec3(0.8, 0.1, 0.0)),
+        &square,
+        &Transform::default(),
+    )
+    .unwrap();
+    assert!(contact.normal.abs_diff_eq(Vec2::X, 1e-5));
+    assert!((contact.depth - 0.2).abs() < 1e-5);
+
+    let circle = Collider::Circle(0.5);
+    let contact = collide(
+        &circle,
+        &Transform::translation(glam::vec3(0.0, -0.9, 0.0)),
+        &square,
+        &Transform::rotation(glam::Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+    )
+    .unwrap();
+    assert!(contact.normal.abs_diff_eq(Vec2::NEG_Y, 1e-5));
+    assert!((contact.depth - (1.0 - 0.9 + 0.5_f32.sqrt() - 0.5)).abs() < 1e-5);
+
+    assert!(collide(
+        &circle,
+        &Transform::translation(glam::vec3(1.1, 0.0, 0.0)),
+        &circle,
+        &Transform::default(),
+    )
+    .is_none());
+}
+
+#[test]
+fn spatial_hash_test() {
+    let mut grid = SpatialHash::new(1.0);
+    grid.insert(0, Rect::from_center_size(Vec2::ZERO, Vec2::ONE));
+    grid.insert(1, Rect::from_center_size(Vec2::new(0.5, 0.5), Vec2::ONE));
+    grid.insert(2, Rect::from_center_size(Vec2::new(10.0, 0.0), Vec2::ONE));
+    assert_eq!(grid.pairs(), vec![(0, 1)]);
+    assert_eq!(
+        grid.query(&Rect::from_center_size(
+            Vec2::new(9.0, 0.0),
+            Vec2::splat(2.0)
+        )),
+        vec![2]
+    );
+
+    grid.insert(2, Rect::from_center_size(Vec2::new(0.0, 0.5), Vec2::ONE));
+    assert_eq!(grid.pairs().len(), 3);
+    grid.remove(0);
+    assert_eq!(grid.pairs().len(), 1);
+
+    // Huge and non-finite bounds are checked against everything instead of filling cells
+    grid.insert(3, Rect::from_center_size(Vec2::ZERO, Vec2::splat(1e30)));
+    grid.insert(
+        4,
+        Rect::from_center_size(Vec2::ZERO, Vec2::splat(f32::INFINITY)),
+    );
+    assert_eq!(grid.large.len(), 2);
+    // Non-finite bounds never overlap anything
+    assert_eq!(grid.pairs().len(), 3);
+    let mut found = grid.query(&Rect::from_center_size(Vec2::new(10.0, 0.0), Vec2::ONE));
+    found.sort_unstable();
+    assert_eq!(found, vec![3]);
+    assert_eq!(
+        grid.query(&Rect::from_center_size(Vec2::ZERO, Vec2::splat(1e9)))
+            .len(),
+        3
+    );
+    grid.remove(3);
+    assert_eq!(grid.large, vec![4]);
+    assert!(std::panic::catch_unwind(|| SpatialHash::<u32>::new(0.0)).is_err());
+}
@@ -8,15 +8,16 @@ var texture: texture_2d<f32>;
 @group(1)@binding(1)
 var texture_sampler: sampler;
 
-struct Transform {
+struct Instance {
     @location(0) row0: vec4<f32>,
     @location(1) row1: vec4<f32>,
     @location(2) row2: vec4<f32>,
     @location(3) row3: vec4<f32>,
+    @location(6) tint: vec4<f32>,
 }
 
-fn transform_to_mat(transform: Transform) -> mat4x4<f32> {
-    return mat4x4<f32>(transform.row0, transform.row1, transform.row2, transform.row3);
+fn instance_to_mat(instance: Instance) -> mat4x4<f32> {
+    return mat4x4<f32>(instance.row0, instance.row1, instance.row2, instance.row3);
 }
 
 struct ColorInput {
@@ -30,11 +31,11 @@ struct ColorOutput {
 }
 
 @vertex
-fn color_vertex(input: ColorInput, transform: Transform) -> ColorOutput {
-    let transform_mat = transform_to_mat(transform);
+fn color_vertex(input: ColorInput, instance: Instance) -> ColorOutput {
+    let transform_mat = instance_to_mat(instance);
     var output: ColorOutput;
     output.pos = projection * camera * transform_mat * vec4<f32>(input.pos, 1.0);
-    output.color = input.color;
+    output.color = input.color * instance.tint;
     return output;
 }
 
@@ -51,14 +52,16 @@ struct TextureInput {
 struct TextureOutput {
     @builtin(position) pos: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
 }
 
 @vertex
-fn texture_vertex(input: TextureInput, transform: Transform) -> TextureOutput {
-    let transform_mat = transform_to_mat(transform);
+fn texture_vertex(input: TextureInput, instance: Instance) -> TextureOutput {
+    let transform_mat = instance_to_mat(instance);
     var output: TextureOutput;
     output.pos = projection * camera * transform_mat * vec4<f32>(input.pos, 1.0);
     output.tex_coords = input.tex_coords;
+    output.tint = instance.tint;
     return output;
 }
 
@@ -68,6 +71,17 @@ fn texture_fragment(output: TextureOutput) -> @location(0) vec4<f32> {
     if (color.a == 0.0) {
         discard;
     } else {
-        return color;
+        return color * output.tint;
+    }
+}
+
+// Single channel textures are masks, where the red channel is how much of the tint covers the pixel
+@fragment
+fn mask_fragment(output: TextureOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(texture, texture_sampler, output.tex_coords).r;
+    if (coverage == 0.0) {
+        discard;
+    } else {
+        return vec4<f32>(1.0, 1.0, 1.0, coverage) * output.tint;
     }
 }
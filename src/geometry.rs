@@ -0,0 +1,390 @@
+//! Shapes and intersection tests between them.
+
+use glam::{vec2, Vec2};
+
+use crate::transform::{Transform, Transform2D};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub pos: Vec2,
+    pub size: Vec2,
+}
+
+impl Rect {
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x >= self.pos.x
+            && point.x < self.pos.x + self.size.x
+            && point.y >= self.pos.y
+            && point.y < self.pos.y + self.size.y
+    }
+
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.pos.x < other.pos.x + other.size.x
+            && self.pos.x + self.size.x > other.pos.x
+            && self.pos.y < other.pos.y + other.size.y
+            && self.pos.y + self.size.y > other.pos.y
+    }
+
+    /// Creates a rectangle centred on a point.
+    #[must_use]
+    pub fn from_center_size(center: Vec2, size: Vec2) -> Self {
+        Self {
+            pos: center - size / 2.0,
+            size,
+        }
+    }
+
+    /// Creates the smallest rectangle containing two points.
+    #[must_use]
+    pub fn from_corners(a: Vec2, b: Vec2) -> Self {
+        Self {
+            pos: a.min(b),
+            size: (a - b).abs(),
+        }
+    }
+
+    /// The corner with the smallest coordinates.
+    #[must_use]
+    pub fn min(&self) -> Vec2 {
+        self.pos
+    }
+
+    /// The corner with the largest coordinates.
+    #[must_use]
+    pub fn max(&self) -> Vec2 {
+        self.pos + self.size
+    }
+
+    #[must_use]
+    pub fn center(&self) -> Vec2 {
+        self.pos + self.size / 2.0
+    }
+
+    /// The area the two rectangles share, or `None` if they don't overlap.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other)
+            .then(|| Self::from_corners(self.min().max(other.min()), self.max().min(other.max())))
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_corners(self.min().min(other.min()), self.max().max(other.max()))
+    }
+
+    /// Grows every side outwards by `amount`, or shrinks if it is negative.
+    #[must_use]
+    pub fn expand(&self, amount: f32) -> Self {
+        Self {
+            pos: self.pos - amount,
+            size: (self.size + amount * 2.0).max(Vec2::ZERO),
+        }
+    }
+
+    /// Moves the rectangle by a transform, returning the rotated box it becomes. Rotations that
+    /// aren't around the Z axis are ignored.
+    #[must_use]
+    pub fn transformed(&self, transform: &Transform) -> OrientedRect {
+        let transform = Transform2D::from(*transform);
+        OrientedRect {
+            center: transform.transform_point(self.center()),
+            half_size: (self.size * transform.scale).abs() / 2.0,
+            angle: transform.angle,
+        }
+    }
+}
+
+impl Default for Rect {
+    fn default() -> Self {
+        Self {
+            pos: Vec2::ZERO,
+            size: Vec2::ONE,
+        }
+    }
+}
+
+/// A rectangle that has been rotated around its centre.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrientedRect {
+    pub center: Vec2,
+    pub half_size: Vec2,
+    /// The counter-clockwise rotation in radians.
+    pub angle: f32,
+}
+
+impl OrientedRect {
+    /// The corners in counter-clockwise order, starting from the bottom left before rotation.
+    #[must_use]
+    pub fn corners(&self) -> [Vec2; 4] {
+        let rotation = Vec2::from_angle(self.angle);
+        [
+            vec2(-1.0, -1.0),
+            vec2(1.0, -1.0),
+            vec2(1.0, 1.0),
+            vec2(-1.0, 1.0),
+        ]
+        .map(|corner| self.center + rotation.rotate(corner * self.half_size))
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        let local = Vec2::from_angle(-self.angle).rotate(point - self.center);
+        local.abs().cmple(self.half_size).all()
+    }
+
+    /// The smallest unrotated rectangle containing this one.
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        let corners = self.corners();
+        let min = corners.into_iter().reduce(Vec2::min).unwrap();
+        let max = corners.into_iter().reduce(Vec2::max).unwrap();
+        Rect::from_corners(min, max)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    #[must_use]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let radius = self.radius + other.radius;
+        self.center.distance_squared(other.center) < radius * radius
+    }
+
+    #[must_use]
+    pub fn overlaps_rect(&self, rect: &Rect) -> bool {
+        let closest = self.center.clamp(rect.min(), rect.max());
+        self.center.distance_squared(closest) < self.radius * self.radius
+    }
+
+    /// The smallest rectangle containing the circle.
+    #[must_use]
+    pub fn bounds(&self) -> Rect {
+        Rect::from_center_size(self.center, Vec2::splat(self.radius * 2.0))
+    }
+}
+
+/// A straight line between two points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    #[must_use]
+    pub fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
+
+    /// The point on the segment closest to `point`.
+    #[must_use]
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let direction = self.end - self.start;
+        let length_squared = direction.length_squared();
+        if length_squared == 0.0 {
+            return self.start;
+        }
+        let t = ((point - self.start).dot(direction) / length_squared).clamp(0.0, 1.0);
+        self.start + direction * t
+    }
+
+    /// The point where the two segments cross, or `None` if they don't. Parallel segments never
+    /// cross.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Option<Vec2> {
+        let t = line_intersection(self.start, self.end - self.start, other)?;
+        (0.0..=1.0)
+            .contains(&t)
+            .then(|| self.start + (self.end - self.start) * t)
+    }
+
+    #[must_use]
+    pub fn intersects_circle(&self, circle: &Circle) -> bool {
+        circle.contains(self.closest_point(circle.center))
+    }
+}
+
+/// A half-infinite line starting at `origin`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec2,
+    /// The direction of the ray, distances are measured in multiples of its length.
+    pub direction: Vec2,
+}
+
+impl Ray {
+    /// The point `distance` along the ray.
+    #[must_use]
+    pub fn at(&self, distance: f32) -> Vec2 {
+        self.origin + self.direction * distance
+    }
+
+    /// The distance along the ray where it first enters the rectangle, which is 0 if it starts
+    /// inside.
+    #[must_use]
+    pub fn cast_rect(&self, rect: &Rect) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let a = (rect.min() - self.origin) * inverse;
+        let b = (rect.max() - self.origin) * inverse;
+        // NaNs from rays parallel to an edge are ignored by `max` and `min`
+        let near = a.min(b).max_element().max(0.0);
+        let far = a.max(b).min_element();
+        (near <= far).then_some(near)
+    }
+
+    /// The distance along the ray where it first enters the circle, which is 0 if it starts
+    /// inside.
+    #[must_use]
+    pub fn cast_circle(&self, circle: &Circle) -> Option<f32> {
+        if circle.contains(self.origin) {
+            return Some(0.0);
+        }
+        let offset = self.origin - circle.center;
+        let a = self.direction.length_squared();
+        let b = offset.dot(self.direction);
+        let c = offset.length_squared() - circle.radius * circle.radius;
+        let discriminant = b * b - a * c;
+        if a == 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let distance = (-b - discriminant.sqrt()) / a;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// The distance along the ray where it crosses the segment.
+    #[must_use]
+    pub fn cast_segment(&self, segment: &Segment) -> Option<f32> {
+        let t = line_intersection(self.origin, self.direction, segment)?;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// How far along `direction` from `start` the segment is crossed, or `None` if the line misses it.
+fn line_intersection(start: Vec2, direction: Vec2, segment: &Segment) -> Option<f32> {
+    let other = segment.end - segment.start;
+    let denominator = direction.perp_dot(other);
+    if denominator == 0.0 {
+        return None;
+    }
+    let t = (segment.start - start).perp_dot(other) / denominator;
+    let u = (segment.start - start).perp_dot(direction) / denominator;
+    (0.0..=1.0).contains(&u).then_some(t)
+}
+
+#[test]
+fn rect_test() {
+    assert!(Rect {
+        pos: vec2(1.0, 1.0),
+        size: vec2(2.0, 2.0)
+    }
+    .contains(vec2(1.0, 1.05)));
+    assert!(Rect {
+        pos: vec2(1.0, 1.0),
+        size: vec2(2.0, 2.0)
+    }
+    .overlaps(&Rect {
+        pos: vec2(1.0, 1.0),
+        size: vec2(2.0, 2.0)
+    }));
+}
+
+#[test]
+fn rect_operations_test() {
+    let a = Rect::from_corners(vec2(2.0, 2.0), vec2(0.0, 0.0));
+    let b = Rect::from_center_size(vec2(2.0, 2.0), vec2(2.0, 2.0));
+    assert_eq!(a.center(), vec2(1.0, 1.0));
+    assert_eq!(
+        a.intersection(&b),
+        Some(Rect::from_corners(Vec2::ONE, vec2(2.0, 2.0)))
+    );
+    assert_eq!(a.union(&b), Rect::from_corners(Vec2::ZERO, vec2(3.0, 3.0)));
+    assert_eq!(
+        a.expand(1.0),
+        Rect::from_corners(Vec2::NEG_ONE, vec2(3.0, 3.0))
+    );
+    assert_eq!(
+        a.intersection(&Rect::from_center_size(vec2(5.0, 5.0), Vec2::ONE)),
+        None
+    );
+
+    let oriented = Rect::from_center_size(Vec2::ZERO, vec2(2.0, 1.0)).transformed(
+        &Transform2D::position(vec2(1.0, 0.0))
+            .with_angle(std::f32::consts::FRAC_PI_2)
+            .into(),
+    );
+    assert!(oriented.contains(vec2(1.0, 0.9)));
+    assert!(!oriented.contains(vec2(1.9, 0.0)));
+    let bounds = oriented.bounds();
+    assert!(bounds.pos.abs_diff_eq(vec2(0.5, -1.0), 1e-5));
+    assert!(bounds.size.abs_diff_eq(vec2(1.0, 2.0), 1e-5));
+}
+
+#[test]
+fn circle_test() {
+    let circle = Circle {
+        center: Vec2::ZERO,
+        radius: 1.0,
+    };
+    assert!(circle.contains(vec2(0.5, 0.5)));
+    assert!(circle.overlaps(&Circle {
+        center: vec2(1.5, 0.0),
+        radius: 1.0,
+    }));
+    assert!(circle.overlaps_rect(&Rect::from_corners(vec2(0.9, -5.0), vec2(2.0, 5.0))));
+    assert!(!circle.overlaps_rect(&Rect::from_corners(vec2(0.9, 0.9), vec2(2.0, 2.0))));
+}
+
+#[test]
+fn segment_and_ray_test() {
+    let a = Segment {
+        start: vec2(-1.0, 0.0),
+        end: vec2(1.0, 0.0),
+    };
+    let b = Segment {
+        start: vec2(0.5, -1.0),
+        end: vec2(0.5, 1.0),
+    };
+    assert_eq!(a.intersection(&b), Some(vec2(0.5, 0.0)));
+    assert_eq!(
+        a.intersection(&Segment {
+            start: vec2(-1.0, 1.0),
+            end: vec2(1.0, 1.0),
+        }),
+        None
+    );
+    assert_eq!(a.closest_point(vec2(3.0, 2.0)), vec2(1.0, 0.0));
+
+    let ray = Ray {
+        origin: vec2(-5.0, 0.0),
+        direction: Vec2::X,
+    };
+    assert_eq!(ray.cast_segment(&b), Some(5.5));
+    assert_eq!(
+        ray.cast_rect(&Rect::from_center_size(Vec2::ZERO, Vec2::splat(2.0))),
+        Some(4.0)
+    );
+    assert_eq!(
+        ray.cast_circle(&Circle {
+            center: Vec2::ZERO,
+            radius: 1.0,
+        }),
+        Some(4.0)
+    );
+    assert_eq!(
+        ray.cast_rect(&Rect::from_center_size(vec2(0.0, 3.0), Vec2::ONE)),
+        None
+    );
+}
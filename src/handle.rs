@@ -0,0 +1,126 @@
+//! Storage that hands out handles which stop working once their value is removed.
+
+use slab::Slab;
+
+/// A reference to a value in a [`HandleSlab`], made of the slot of the value and the generation
+/// it was inserted in.
+pub(crate) trait Handle: Copy {
+    fn new(index: usize, generation: u32) -> Self;
+    fn index(self) -> usize;
+    fn generation(self) -> u32;
+}
+
+/// Implements [`Handle`] for a struct with `index` and `generation` fields.
+macro_rules! impl_handle {
+    ($handle:ty) => {
+        impl $crate::handle::Handle for $handle {
+            fn new(index: usize, generation: u32) -> Self {
+                Self { index, generation }
+            }
+
+            fn index(self) -> usize {
+                self.index
+            }
+
+            fn generation(self) -> u32 {
+                self.generation
+            }
+        }
+    };
+}
+pub(crate) use impl_handle;
+
+/// A slab that rejects handles once their slot has been removed, even if it has been reused since.
+#[derive(Debug)]
+pub(crate) struct HandleSlab<H, T> {
+    slots: Slab<(u32, T)>,
+    generation: u32,
+    handle: std::marker::PhantomData<H>,
+}
+
+impl<H, T> Default for HandleSlab<H, T> {
+    fn default() -> Self {
+        Self {
+            slots: Slab::new(),
+            generation: 0,
+            handle: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<H: Handle, T> HandleSlab<H, T> {
+    pub(crate) fn insert(&mut self, value: T) -> H {
+        self.generation = self.generation.wrapping_add(1);
+        H::new(self.slots.insert((self.generation, value)), self.generation)
+    }
+
+    pub(crate) fn get(&self, handle: H) -> Option<&T> {
+        match self.slots.get(handle.index()) {
+            Some((generation, value)) if *generation == handle.generation() => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, handle: H) -> Option<&mut T> {
+        match self.slots.get_mut(handle.index()) {
+            Some((generation, value)) if *generation == handle.generation() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Two different values at once, or `None` if either handle is stale or they're the same.
+    pub(crate) fn get2_mut(&mut self, a: H, b: H) -> Option<(&mut T, &mut T)> {
+        let ((a_generation, a_value), (b_generation, b_value)) =
+            self.slots.get2_mut(a.index(), b.index())?;
+        (*a_generation == a.generation() && *b_generation == b.generation())
+            .then_some((a_value, b_value))
+    }
+
+    pub(crate) fn remove(&mut self, handle: H) -> Option<T> {
+        self.get(handle)?;
+        Some(self.slots.remove(handle.index()).1)
+    }
+
+    pub(crate) fn contains(&self, handle: H) -> bool {
+        self.get(handle).is_some()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (H, &T)> {
+        self.slots
+            .iter()
+            .map(|(index, (generation, value))| (H::new(index, *generation), value))
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (H, &mut T)> {
+        self.slots
+            .iter_mut()
+            .map(|(index, (generation, value))| (H::new(index, *generation), value))
+    }
+}
+
+#[test]
+fn handle_slab_test() {
+    let mut slab = HandleSlab::<crate::sprite::SpriteHandle, _>::default();
+    let a = slab.insert("a");
+    let b = slab.insert("b");
+    assert_eq!(slab.get(a), Some(&"a"));
+    assert_eq!(slab.remove(a), Some("a"));
+    assert_eq!(slab.remove(a), None);
+
+    // The slot of `a` is reused, but the old handle still refers to nothing
+    let c = slab.insert("c");
+    assert_eq!(c.index, a.index);
+    assert_eq!(slab.get(a), None);
+    assert_eq!(slab.get_mut(a), None);
+    assert!(!slab.contains(a));
+    assert!(slab.contains(b) && slab.contains(c));
+    *slab.get_mut(c).unwrap() = "d";
+    assert_eq!(
+        slab.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+        ["d", "b"]
+    );
+}
@@ -1,18 +1,33 @@
+pub mod animation;
+pub mod assets;
+pub mod atlas;
 pub mod camera;
+pub mod collision;
+pub mod geometry;
+mod handle;
 pub mod input;
+pub mod physics;
 pub mod renderer;
+pub mod scene;
 pub mod sprite;
 pub mod text;
 pub mod texture;
 pub mod transform;
+pub mod tween;
+pub mod viewport;
+mod watch;
 
 use std::time::{Duration, Instant};
 
+use assets::Assets;
 use glam::{uvec2, UVec2};
 use input::Input;
+use physics::PhysicsWorld;
 use renderer::Renderer;
+use scene::Scene;
 use text::TextManager;
 use texture::TextureManager;
+use tween::Tweens;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
@@ -20,6 +35,7 @@ use winit::{
 };
 
 /// Consumes the [`Game`] and runs it. Should be the last function in the main function.
+#[allow(clippy::collapsible_match)]
 pub fn run(mut game: impl Game + 'static) -> ! {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new().build(&event_loop).unwrap();
@@ -29,10 +45,14 @@ pub fn run(mut game: impl Game + 'static) -> ! {
         renderer: pollster::block_on(Renderer::new(
             window,
             camera::Camera::default(),
-            renderer::Projection::FixedWidth(2.0),
+            renderer::ProjectionMode::FixedWidth(2.0).into(),
         )),
+        scene: Scene::default(),
+        physics: PhysicsWorld::default(),
+        assets: Assets::new(),
         texture_manager: TextureManager::default(),
         text_manager: TextManager::new(),
+        tweens: Tweens::default(),
         exit_code: None,
         delta_time: Duration::default(),
         start_time: Instant::now(),
@@ -40,13 +60,19 @@ pub fn run(mut game: impl Game + 'static) -> ! {
     };
 
     game.init(&mut data);
+    data.scene.update(&mut data.renderer);
 
     let mut last_update = Instant::now();
     event_loop.run(move |event, _, control_flow| match event {
         Event::MainEventsCleared => {
             if last_update.elapsed() >= data.frame_length {
                 data.delta_time = Instant::now().duration_since(last_update);
+                data.texture_manager.poll();
+                data.renderer.hot_reload();
                 game.update(&mut data);
+                data.physics.update(data.delta_time, &mut data.renderer);
+                Tweens::update(&mut data);
+                data.scene.update(&mut data.renderer);
                 if let Some(code) = data.exit_code {
                     *control_flow = ControlFlow::ExitWithCode(code);
                 }
@@ -93,15 +119,23 @@ pub fn run(mut game: impl Game + 'static) -> ! {
 }
 
 /// Common game state that is handed to the [`Game`] state during updates, and allowing control.
-pub struct GameData<'a> {
+pub struct GameData {
     /// A manager for reading player input.
     pub input: Input,
     /// A manager to handle drawing graphics.
     pub renderer: Renderer,
+    /// A hierarchy of transforms that is applied to sprites after every update.
+    pub scene: Scene,
+    /// Rigid bodies that are stepped at a fixed timestep after every update.
+    pub physics: PhysicsWorld,
+    /// Mounted asset sources and the assets loaded from them.
+    pub assets: Assets,
     /// Stores textures to minimise reallocation.
-    pub texture_manager: TextureManager<'a>,
+    pub texture_manager: TextureManager,
     /// A manager that stores fonts and renders text into images.
     pub text_manager: TextManager,
+    /// Animations that are advanced after every update.
+    pub tweens: Tweens,
     /// If `None` does nothing, but if set to `Some` then the program will exit, returning the `i32`.
     pub exit_code: Option<i32>,
     /// The time since the last update.
@@ -112,7 +146,7 @@ pub struct GameData<'a> {
     pub frame_length: Duration,
 }
 
-impl GameData<'_> {
+impl GameData {
     /// Shorthand to set `exit_code` to 0.
     pub fn exit(&mut self) {
         self.exit_code = Some(0);
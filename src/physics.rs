@@ -0,0 +1,414 @@
+//! Simple rigid body physics without rotation.
+
+use std::time::Duration;
+
+use fxhash::FxHashMap;
+use glam::{Vec2, Vec3Swizzles};
+
+use crate::{
+    collision::{Collider, Contact, Shape, SpatialHash},
+    handle::{impl_handle, HandleSlab},
+    renderer::Renderer,
+    sprite::SpriteHandle,
+    transform::Transform,
+};
+
+/// How a body reacts to forces and collisions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BodyType {
+    /// Never moves, like the ground.
+    Static,
+    /// Moves with its velocity but isn't affected by gravity or collisions, like a moving platform.
+    Kinematic,
+    /// Affected by gravity and pushed around by collisions.
+    #[default]
+    Dynamic,
+}
+
+/// A reference to a body in a [`PhysicsWorld`], which stops referring to anything once the body
+/// is removed, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BodyId {
+    index: usize,
+    generation: u32,
+}
+
+impl_handle!(BodyId);
+
+/// An object that is moved by a [`PhysicsWorld`].
+#[derive(Clone, Debug)]
+pub struct Body {
+    pub ty: BodyType,
+    pub collider: Collider,
+    /// Only the translation is changed by physics, the rotation and scale still affect the
+    /// collider.
+    pub transform: Transform,
+    pub velocity: Vec2,
+    /// Applied every step on top of gravity, such as from player input.
+    pub acceleration: Vec2,
+    pub mass: f32,
+    pub gravity_scale: f32,
+    /// How bouncy the body is, where 0 doesn't bounce and 1 bounces without losing speed.
+    pub restitution: f32,
+    pub friction: f32,
+    /// A sprite and the index of the instance of it whose transform follows the body.
+    pub sprite: Option<(SpriteHandle, usize)>,
+}
+
+impl Body {
+    #[must_use]
+    pub fn new(ty: BodyType, collider: Collider) -> Self {
+        Self {
+            ty,
+            collider,
+            transform: Transform::default(),
+            velocity: Vec2::ZERO,
+            acceleration: Vec2::ZERO,
+            mass: 1.0,
+            gravity_scale: 1.0,
+            restitution: 0.0,
+            friction: 0.5,
+            sprite: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_transform(self, transform: Transform) -> Self {
+        Self { transform, ..self }
+    }
+
+    #[must_use]
+    pub fn with_velocity(self, velocity: Vec2) -> Self {
+        Self { velocity, ..self }
+    }
+
+    #[must_use]
+    pub fn with_acceleration(self, acceleration: Vec2) -> Self {
+        Self {
+            acceleration,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_mass(self, mass: f32) -> Self {
+        Self { mass, ..self }
+    }
+
+    #[must_use]
+    pub fn with_gravity_scale(self, gravity_scale: f32) -> Self {
+        Self {
+            gravity_scale,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_restitution(self, restitution: f32) -> Self {
+        Self {
+            restitution,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_friction(self, friction: f32) -> Self {
+        Self { friction, ..self }
+    }
+
+    #[must_use]
+    pub fn with_sprite(self, sprite: SpriteHandle, instance: usize) -> Self {
+        Self {
+            sprite: Some((sprite, instance)),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn position(&self) -> Vec2 {
+        self.transform.translation.xy()
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.transform.translation = position.extend(self.transform.translation.z);
+    }
+
+    /// The collider placed at the body's transform.
+    #[must_use]
+    pub fn shape(&self) -> Shape {
+        self.collider.to_shape(&self.transform)
+    }
+
+    fn inverse_mass(&self) -> f32 {
+        match self.ty {
+            BodyType::Dynamic if self.mass > 0.0 => self.mass.recip(),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Two bodies that were touching during the last step, where the contact pushes `a` out of `b`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub a: BodyId,
+    pub b: BodyId,
+    pub contact: Contact,
+}
+
+/// Moves bodies at a fixed timestep and separates the ones that collide.
+#[derive(Debug)]
+pub struct PhysicsWorld {
+    pub gravity: Vec2,
+    /// The length of each step, which stays the same no matter the frame rate.
+    pub timestep: Duration,
+    /// The most steps taken in one update, so a slow frame doesn't cause even slower frames.
+    pub max_steps: u32,
+    bodies: HandleSlab<BodyId, Body>,
+    accumulator: Duration,
+    broadphase: SpatialHash<BodyId>,
+    collisions: Vec<Collision>,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicsWorld {
+    /// Creates a world with gravity pointing down, stepping 60 times a second.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, -9.81),
+            timestep: Duration::from_secs_f32(1.0 / 60.0),
+            max_steps: 8,
+            bodies: HandleSlab::default(),
+            accumulator: Duration::ZERO,
+            broadphase: SpatialHash::new(1.0),
+            collisions: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_gravity(self, gravity: Vec2) -> Self {
+        Self { gravity, ..self }
+    }
+
+    #[must_use]
+    pub fn with_timestep(self, timestep: Duration) -> Self {
+        Self { timestep, ..self }
+    }
+
+    /// Sets the cell size of the broadphase grid, which should be around the size of a typical
+    /// body.
+    #[must_use]
+    pub fn with_cell_size(self, cell_size: f32) -> Self {
+        Self {
+            broadphase: SpatialHash::new(cell_size),
+            ..self
+        }
+    }
+
+    pub fn insert(&mut self, body: Body) -> BodyId {
+        self.bodies.insert(body)
+    }
+
+    pub fn remove(&mut self, id: BodyId) -> Option<Body> {
+        self.bodies.remove(id)
+    }
+
+    #[must_use]
+    pub fn get(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.get(id)
+    }
+
+    #[must_use]
+    pub fn get_mut(&mut self, id: BodyId) -> Option<&mut Body> {
+        self.bodies.get_mut(id)
+    }
+
+    #[must_use]
+    pub fn contains(&self, id: BodyId) -> bool {
+        self.bodies.contains(id)
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyId, &Body)> {
+        self.bodies.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+        self.collisions.clear();
+    }
+
+    /// Every collision found during the last step.
+    #[must_use]
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// The collisions of one body during the last step, with the contacts pushing it out of the
+    /// other body, such as to check if a player is standing on the ground.
+    pub fn collisions_of(&self, id: BodyId) -> impl Iterator<Item = (BodyId, Contact)> + '_ {
+        self.collisions.iter().filter_map(move |collision| {
+            if collision.a == id {
+                Some((collision.b, collision.contact))
+            } else if collision.b == id {
+                Some((collision.a, collision.contact.flip()))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Runs as many steps as fit in the time since the last update, then moves the sprites
+    /// attached to bodies.
+    pub fn update(&mut self, delta: Duration, renderer: &mut Renderer) {
+        if self.timestep.is_zero() {
+            return;
+        }
+        self.accumulator += delta;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep {
+            if steps >= self.max_steps {
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+            self.step();
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+
+        for (_, body) in self.bodies.iter() {
+            if let Some((handle, instance)) = body.sprite {
+                if let Some(sprite) = renderer.get_mut(handle) {
+                    if sprite.transforms.get(instance) != Some(&body.transform) {
+                        if let Some(transform) = sprite.transforms.get_mut(instance) {
+                            *transform = body.transform;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Advances the world by one timestep.
+    pub fn step(&mut self) {
+        let delta = self.timestep.as_secs_f32();
+        for (_, body) in self.bodies.iter_mut() {
+            match body.ty {
+                BodyType::Static => continue,
+                BodyType::Kinematic => {}
+                BodyType::Dynamic => {
+                    body.velocity +=
+                        (self.gravity * body.gravity_scale + body.acceleration) * delta;
+                }
+            }
+            let position = body.position() + body.velocity * delta;
+            body.set_position(position);
+        }
+
+        self.broadphase.clear();
+        let mut shapes = FxHashMap::default();
+        for (id, body) in self.bodies.iter() {
+            let shape = body.shape();
+            self.broadphase.insert(id, shape.bounds());
+            shapes.insert(id, shape);
+        }
+
+        self.collisions.clear();
+        for (a, b) in self.broadphase.pairs() {
+            let (Some(a_body), Some(b_body)) = (self.bodies.get(a), self.bodies.get(b)) else {
+                continue;
+            };
+            if a_body.ty != BodyType::Dynamic && b_body.ty != BodyType::Dynamic {
+                continue;
+            }
+            let Some(contact) = shapes[&a].collide(&shapes[&b]) else {
+                continue;
+            };
+            self.collisions.push(Collision { a, b, contact });
+            self.resolve(a, b, contact);
+        }
+    }
+
+    /// Pushes two bodies apart and bounces them off each other.
+    fn resolve(&mut self, a: BodyId, b: BodyId, contact: Contact) {
+        let Some((a, b)) = self.bodies.get2_mut(a, b) else {
+            return;
+        };
+        let a_inverse = a.inverse_mass();
+        let b_inverse = b.inverse_mass();
+        let total = a_inverse + b_inverse;
+        if total == 0.0 {
+            return;
+        }
+
+        // Leave a little overlap so resting bodies keep touching instead of jittering
+        const SLOP: f32 = 0.001;
+        let correction = contact.normal * (contact.depth - SLOP).max(0.0) / total;
+        let position = a.position() + correction * a_inverse;
+        a.set_position(position);
+        let position = b.position() - correction * b_inverse;
+        b.set_position(position);
+
+        let relative = a.velocity - b.velocity;
+        let normal_speed = relative.dot(contact.normal);
+        if normal_speed >= 0.0 {
+            return;
+        }
+        let restitution = a.restitution.max(b.restitution);
+        let impulse = -(1.0 + restitution) * normal_speed / total;
+        a.velocity += contact.normal * impulse * a_inverse;
+        b.velocity -= contact.normal * impulse * b_inverse;
+
+        let relative = a.velocity - b.velocity;
+        let Some(tangent) =
+            (relative - contact.normal * relative.dot(contact.normal)).try_normalize()
+        else {
+            return;
+        };
+        let limit = impulse * (a.friction * b.friction).sqrt();
+        let friction = (-relative.dot(tangent) / total).clamp(-limit, limit);
+        a.velocity += tangent * friction * a_inverse;
+        b.velocity -= tangent * friction * b_inverse;
+    }
+}
+
+#[test]
+fn physics_test() {
+    let mut world = PhysicsWorld::new();
+    let ground = world.insert(
+        Body::new(BodyType::Static, Collider::Box(Vec2::new(10.0, 1.0)))
+            .with_transform(Transform::translation(glam::Vec3::new(0.0, -0.5, 0.0))),
+    );
+    let crate_ = world.insert(
+        Body::new(BodyType::Dynamic, Collider::sprite())
+            .with_transform(Transform::translation(glam::Vec3::new(0.0, 2.0, 0.0)))
+            .with_velocity(Vec2::new(1.0, 0.0)),
+    );
+    for _ in 0..240 {
+        world.step();
+    }
+
+    let body = world.get(crate_).unwrap();
+    assert!((body.position().y - 0.5).abs() < 0.01);
+    // Friction stops it sliding
+    assert!(body.velocity.length() < 0.01);
+    assert_eq!(world.get(ground).unwrap().position(), Vec2::new(0.0, -0.5));
+    let (other, contact) = world.collisions_of(crate_).next().unwrap();
+    assert_eq!(other, ground);
+    assert!(contact.normal.abs_diff_eq(Vec2::Y, 1e-5));
+
+    let ball = world.insert(
+        Body::new(BodyType::Dynamic, Collider::Circle(0.25))
+            .with_transform(Transform::translation(glam::Vec3::new(3.0, 0.5, 0.0)))
+            .with_velocity(Vec2::new(0.0, -5.0))
+            .with_restitution(1.0),
+    );
+    for _ in 0..3 {
+        world.step();
+    }
+    assert!(world.get(ball).unwrap().velocity.y > 4.0);
+}
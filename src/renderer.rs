@@ -1,17 +1,17 @@
-use std::sync::OnceLock;
-
-use dirtytype::Dirty;
-use glam::{vec2, Mat4};
-use slab::Slab;
-use wgpu::{
-    util::{BufferInitDescriptor, DeviceExt},
-    Device, Queue, Surface, SurfaceConfiguration,
-};
+use std::{fs, path::PathBuf, sync::OnceLock, time::Duration};
+
+use anyhow::{anyhow, Result};
+
+use glam::{uvec2, vec2, Mat4, UVec2, Vec2, Vec3};
+use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
 use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::{
     camera::Camera,
-    sprite::{Sprite, SpriteType},
+    handle::HandleSlab,
+    sprite::{Rect, Space, Sprite, SpriteHandle, SpriteType},
+    viewport::{Viewport, ViewportHandle},
+    watch::FileWatcher,
 };
 
 /// An accessor to global data types for all rendering.
@@ -36,14 +36,16 @@ pub struct Renderer {
     pub window: Window,
 
     pub background: wgpu::Color,
-    pub camera: Dirty<Camera>,
-    camera_buffer: wgpu::Buffer,
-    pub projection: Dirty<Projection>,
-    projection_buffer: wgpu::Buffer,
-    projection_bind_group: wgpu::BindGroup,
-    pub sprites: Slab<Sprite>,
+    /// The areas of the window that sprites are drawn to, in order.
+    viewports: HandleSlab<ViewportHandle, Viewport>,
+    main_viewport: ViewportHandle,
+    sprites: HandleSlab<SpriteHandle, Sprite>,
     color_pipeline: wgpu::RenderPipeline,
     texture_pipeline: wgpu::RenderPipeline,
+    mask_pipeline: wgpu::RenderPipeline,
+    shader_path: Option<PathBuf>,
+    shader_watcher: Option<FileWatcher>,
+    shader_error: Option<anyhow::Error>,
     depth_view: wgpu::TextureView,
 }
 
@@ -83,120 +85,261 @@ impl Renderer {
 
         GLOBALS.set(RendererGlobals { device, queue }).unwrap();
 
-        let projection_buffer =
-            RendererGlobals::get()
-                .device
-                .create_buffer_init(&BufferInitDescriptor {
-                    label: None,
-                    contents: bytemuck::cast_slice(&[projection.as_mat4(window.inner_size())]),
-                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-                });
-        let camera_buffer =
-            RendererGlobals::get()
-                .device
-                .create_buffer_init(&BufferInitDescriptor {
-                    label: None,
-                    contents: bytemuck::cast_slice(&[Mat4::from(&camera)]),
-                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-                });
-        let projection_bind_group_layout = RendererGlobals::get().device.create_bind_group_layout(
-            &wgpu::BindGroupLayoutDescriptor {
-                label: None,
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                ],
-            },
-        );
-        let projection_bind_group =
-            RendererGlobals::get()
-                .device
-                .create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("projection"),
-                    layout: &projection_bind_group_layout,
-                    entries: &[
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: wgpu::BindingResource::Buffer(
-                                projection_buffer.as_entire_buffer_binding(),
-                            ),
-                        },
-                        wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: wgpu::BindingResource::Buffer(
-                                camera_buffer.as_entire_buffer_binding(),
-                            ),
-                        },
-                    ],
-                });
+        let mut viewports = HandleSlab::default();
+        let main_viewport = viewports.insert(Viewport::new(camera, projection));
+        let projection_bind_group_layout = Viewport::bind_group_layout();
+        let shader = Sprite::shader();
 
         Self {
             surface,
             config,
 
             background: wgpu::Color::BLACK,
-            camera: Dirty::new(camera),
-            camera_buffer,
-            projection: Dirty::new(projection),
-            projection_buffer,
-            projection_bind_group,
-            color_pipeline: Sprite::color_pipeline(&projection_bind_group_layout),
-            texture_pipeline: Sprite::texture_pipeline(&projection_bind_group_layout),
+            viewports,
+            main_viewport,
+            color_pipeline: Sprite::color_pipeline(&projection_bind_group_layout, &shader),
+            texture_pipeline: Sprite::texture_pipeline(
+                &projection_bind_group_layout,
+                &shader,
+                false,
+            ),
+            mask_pipeline: Sprite::texture_pipeline(&projection_bind_group_layout, &shader, true),
+            shader_path: None,
+            shader_watcher: None,
+            shader_error: None,
             depth_view: Self::make_depth_texture(window.inner_size()),
-            sprites: Slab::default(),
+            sprites: HandleSlab::default(),
 
             window,
         }
     }
 
+    /// Adds a sprite to be drawn, returning a handle to it.
+    pub fn insert(&mut self, sprite: Sprite) -> SpriteHandle {
+        self.sprites.insert(sprite)
+    }
+
+    /// Gets a sprite, or `None` if it has been removed.
+    #[must_use]
+    pub fn get(&self, handle: SpriteHandle) -> Option<&Sprite> {
+        self.sprites.get(handle)
+    }
+
+    /// Gets a sprite mutably, or `None` if it has been removed.
+    #[must_use]
+    pub fn get_mut(&mut self, handle: SpriteHandle) -> Option<&mut Sprite> {
+        self.sprites.get_mut(handle)
+    }
+
+    /// Removes a sprite, returning it if it hadn't already been removed.
+    pub fn remove(&mut self, handle: SpriteHandle) -> Option<Sprite> {
+        self.sprites.remove(handle)
+    }
+
+    /// Whether the sprite the handle refers to still exists.
+    #[must_use]
+    pub fn contains(&self, handle: SpriteHandle) -> bool {
+        self.sprites.contains(handle)
+    }
+
+    /// Iterates over every sprite.
+    pub fn sprites(&self) -> impl Iterator<Item = (SpriteHandle, &Sprite)> {
+        self.sprites.iter()
+    }
+
+    /// Iterates mutably over every sprite.
+    pub fn sprites_mut(&mut self) -> impl Iterator<Item = (SpriteHandle, &mut Sprite)> {
+        self.sprites.iter_mut()
+    }
+
+    /// Adds a viewport, which is drawn after the existing ones.
+    pub fn insert_viewport(&mut self, viewport: Viewport) -> ViewportHandle {
+        self.viewports.insert(viewport)
+    }
+
+    /// Gets a viewport, or `None` if it has been removed.
+    #[must_use]
+    pub fn viewport(&self, handle: ViewportHandle) -> Option<&Viewport> {
+        self.viewports.get(handle)
+    }
+
+    /// Gets a viewport mutably, or `None` if it has been removed.
+    #[must_use]
+    pub fn viewport_mut(&mut self, handle: ViewportHandle) -> Option<&mut Viewport> {
+        self.viewports.get_mut(handle)
+    }
+
+    /// Removes a viewport, returning it if it hadn't already been removed. The main viewport can't
+    /// be removed, so `None` is returned for it.
+    pub fn remove_viewport(&mut self, handle: ViewportHandle) -> Option<Viewport> {
+        if handle == self.main_viewport {
+            return None;
+        }
+        self.viewports.remove(handle)
+    }
+
+    /// Iterates over every viewport in the order they're drawn.
+    pub fn viewports(&self) -> impl Iterator<Item = (ViewportHandle, &Viewport)> {
+        self.viewports.iter()
+    }
+
+    /// Iterates mutably over every viewport in the order they're drawn.
+    pub fn viewports_mut(&mut self) -> impl Iterator<Item = (ViewportHandle, &mut Viewport)> {
+        self.viewports.iter_mut()
+    }
+
+    /// The handle of the main viewport, which is never removed.
+    #[must_use]
+    pub fn main_viewport_handle(&self) -> ViewportHandle {
+        self.main_viewport
+    }
+
+    /// The first viewport, which covers the whole window unless changed.
+    #[must_use]
+    pub fn main_viewport(&self) -> &Viewport {
+        self.viewports.get(self.main_viewport).unwrap()
+    }
+
+    /// The first viewport, which covers the whole window unless changed.
+    #[must_use]
+    pub fn main_viewport_mut(&mut self) -> &mut Viewport {
+        self.viewports.get_mut(self.main_viewport).unwrap()
+    }
+
+    /// The last drawn viewport that contains a position on the window in physical pixels.
+    #[must_use]
+    pub fn viewport_at(&self, pos: Vec2) -> Option<ViewportHandle> {
+        self.viewports
+            .iter()
+            .filter(|(_, viewport)| viewport.contains(self.window.inner_size(), pos))
+            .map(|(handle, _)| handle)
+            .last()
+    }
+
+    /// Converts a position on the window in physical pixels, such as [`Input::mouse_pos`], to a
+    /// position in the world as seen through the main viewport.
+    ///
+    /// [`Input::mouse_pos`]: crate::input::Input::mouse_pos
+    #[must_use]
+    pub fn screen_to_world(&self, pos: Vec2) -> Vec2 {
+        self.main_viewport()
+            .screen_to_world(self.window.inner_size(), pos)
+    }
+
+    /// Converts a position in the world to a position on the window in physical pixels, as seen
+    /// through the main viewport.
+    #[must_use]
+    pub fn world_to_screen(&self, pos: Vec3) -> Vec2 {
+        self.main_viewport()
+            .world_to_screen(self.window.inner_size(), pos)
+    }
+
+    /// The size of the area of the world that is visible through the main viewport.
+    #[must_use]
+    pub fn visible_size(&self) -> Vec2 {
+        self.main_viewport().visible_size(self.window.inner_size())
+    }
+
+    /// Draws sprites with a WGSL shader file instead of the built in one. It needs the same entry
+    /// points and bindings as the built in shader, and if it fails to compile the current shader
+    /// is kept.
+    pub fn load_shader(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let path = path.into();
+        let source = fs::read_to_string(&path)?;
+        self.set_shader(Some(&source))?;
+        if let Some(watcher) = &mut self.shader_watcher {
+            if let Some(old) = &self.shader_path {
+                watcher.unwatch(old);
+            }
+            watcher.watch(&path);
+        }
+        self.shader_path = Some(path);
+        self.shader_error = None;
+        Ok(())
+    }
+
+    /// Goes back to drawing sprites with the built in shader.
+    pub fn reset_shader(&mut self) {
+        self.set_shader(None).unwrap();
+        if let (Some(watcher), Some(path)) = (&mut self.shader_watcher, &self.shader_path) {
+            watcher.unwatch(path);
+        }
+        self.shader_path = None;
+        self.shader_error = None;
+    }
+
+    /// Reloads the shader from [`Renderer::load_shader`] when its file changes, checking at most
+    /// once per `interval`, or stops if `None`.
+    pub fn set_hot_reload(&mut self, interval: Option<Duration>) {
+        self.shader_watcher = interval.map(|interval| {
+            let mut watcher = FileWatcher::new(interval);
+            if let Some(path) = &self.shader_path {
+                watcher.watch(path);
+            }
+            watcher
+        });
+    }
+
+    pub(crate) fn hot_reload(&mut self) {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return;
+        };
+        if let Some(path) = watcher.changed().pop() {
+            self.shader_error = fs::read_to_string(&path)
+                .map_err(Into::into)
+                .and_then(|source| self.set_shader(Some(&source)))
+                .err();
+        }
+    }
+
+    /// Why the shader file couldn't be reloaded the last time it changed, in which case the
+    /// previous shader is still being used.
+    #[must_use]
+    pub fn shader_error(&self) -> Option<&anyhow::Error> {
+        self.shader_error.as_ref()
+    }
+
+    /// Rebuilds the pipelines with a WGSL shader, or the built in one if `None`.
+    fn set_shader(&mut self, source: Option<&str>) -> Result<()> {
+        let device = &RendererGlobals::get().device;
+        // Catch errors instead of panicking so a typo doesn't crash the game while editing
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = match source {
+            Some(source) => device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("sprite shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            }),
+            None => Sprite::shader(),
+        };
+        let layout = Viewport::bind_group_layout();
+        let color_pipeline = Sprite::color_pipeline(&layout, &shader);
+        let texture_pipeline = Sprite::texture_pipeline(&layout, &shader, false);
+        let mask_pipeline = Sprite::texture_pipeline(&layout, &shader, true);
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(anyhow!("{error}"));
+        }
+        self.color_pipeline = color_pipeline;
+        self.texture_pipeline = texture_pipeline;
+        self.mask_pipeline = mask_pipeline;
+        Ok(())
+    }
+
     pub(crate) fn resize(&mut self, size: PhysicalSize<u32>) {
         self.config.width = size.width;
         self.config.height = size.height;
-        RendererGlobals::get().queue.write_buffer(
-            &self.projection_buffer,
-            0,
-            bytemuck::cast_slice(&[self.projection.as_mat4(self.window.inner_size())]),
-        );
         self.depth_view = Self::make_depth_texture(size);
         self.surface
             .configure(&RendererGlobals::get().device, &self.config);
     }
 
     pub(crate) fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        // Can't use `Dirty::clean` because it requires weird mutability issues
-        if self.camera.dirty {
-            RendererGlobals::get().queue.write_buffer(
-                &self.camera_buffer,
-                0,
-                bytemuck::cast_slice(&[Mat4::from(&*self.camera)]),
-            );
-            self.projection.dirty = false;
+        let window_size = self.window.inner_size();
+        for (_, viewport) in self.viewports.iter_mut() {
+            viewport.upload(window_size);
         }
-        if self.projection.dirty {
-            RendererGlobals::get().queue.write_buffer(
-                &self.projection_buffer,
-                0,
-                bytemuck::cast_slice(&[self.projection.as_mat4(self.window.inner_size())]),
-            );
-            self.projection.dirty = false;
+        for (_, model) in self.sprites.iter_mut() {
+            model.update_texture();
+            model.upload_instances();
         }
 
         let current = self.surface.get_current_texture()?;
@@ -207,49 +350,61 @@ impl Renderer {
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("color pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.background),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
-            });
-
-            render_pass.set_bind_group(0, &self.projection_bind_group, &[]);
-            for (_, model) in self.sprites.iter_mut().filter(|(_, sprite)| sprite.visible) {
-                model.transforms.clean(|t| {
-                    RendererGlobals::get().queue.write_buffer(
-                        &model.transform_buffer,
-                        0,
-                        bytemuck::cast_slice(&t.iter().map(Mat4::from).collect::<Vec<_>>()),
-                    );
-                });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("clear pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.background),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        for (_, viewport) in self.viewports.iter() {
+            let outer = viewport.pixel_rect(window_size);
+            let rect = viewport.active_rect(window_size);
+            if rect.size.x < 1.0 || rect.size.y < 1.0 {
+                continue;
+            }
 
-                match &model.ty {
-                    SpriteType::Color => render_pass.set_pipeline(&self.color_pipeline),
-                    SpriteType::Texture(texture) => {
-                        render_pass.set_pipeline(&self.texture_pipeline);
-                        render_pass.set_bind_group(1, texture, &[]);
-                    }
+            if let Some(bars) = viewport.letterbox_bars() {
+                if rect != outer {
+                    let mut render_pass = Self::color_pass(&mut encoder, &view, &self.depth_view);
+                    Self::clip(&mut render_pass, outer);
+                    render_pass.set_bind_group(0, &viewport.identity_bind_group, &[]);
+                    self.draw(&mut render_pass, bars);
+                }
+            }
+
+            // Each viewport and space gets its own pass so that the depth buffer is cleared
+            // between them, keeping screen space sprites on top of the world
+            for (space, bind_group) in [
+                (Space::World, &viewport.bind_group),
+                (Space::Screen, &viewport.screen_bind_group),
+            ] {
+                let mut sprites = self
+                    .sprites
+                    .iter()
+                    .map(|(_, sprite)| sprite)
+                    .filter(|sprite| {
+                        sprite.visible
+                            && sprite.space == space
+                            && sprite.layers & viewport.layers != 0
+                    })
+                    .peekable();
+                if sprites.peek().is_none() {
+                    continue;
+                }
+
+                let mut render_pass = Self::color_pass(&mut encoder, &view, &self.depth_view);
+                Self::clip(&mut render_pass, rect);
+                render_pass.set_bind_group(0, bind_group, &[]);
+                for model in sprites {
+                    self.draw(&mut render_pass, model);
                 }
-                render_pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
-                render_pass.set_vertex_buffer(1, model.transform_buffer.slice(..));
-                render_pass
-                    .set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                render_pass.draw_indexed(0..model.index_count, 0, 0..model.transform_count);
             }
         }
 
@@ -261,6 +416,61 @@ impl Renderer {
         Ok(())
     }
 
+    fn color_pass<'a>(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        depth_view: &'a wgpu::TextureView,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("color pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        })
+    }
+
+    /// Limits drawing to an area of the window in physical pixels.
+    fn clip(render_pass: &mut wgpu::RenderPass, rect: Rect) {
+        render_pass.set_viewport(rect.pos.x, rect.pos.y, rect.size.x, rect.size.y, 0.0, 1.0);
+        render_pass.set_scissor_rect(
+            rect.pos.x as u32,
+            rect.pos.y as u32,
+            rect.size.x as u32,
+            rect.size.y as u32,
+        );
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, model: &'a Sprite) {
+        match &model.ty {
+            SpriteType::Color => render_pass.set_pipeline(&self.color_pipeline),
+            SpriteType::Texture(texture) => {
+                render_pass.set_pipeline(if model.mask {
+                    &self.mask_pipeline
+                } else {
+                    &self.texture_pipeline
+                });
+                render_pass.set_bind_group(1, texture, &[]);
+            }
+        }
+        render_pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, model.transform_buffer.slice(..));
+        render_pass.set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..model.index_count, 0, 0..model.transform_count);
+    }
+
     fn make_depth_texture(size: PhysicalSize<u32>) -> wgpu::TextureView {
         RendererGlobals::get()
             .device
@@ -282,55 +492,336 @@ impl Renderer {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Projection {
+/// How the visible area of the world is fitted to the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectionMode {
+    /// Shows exactly this width and height, stretching to fit the window.
     Absolute(f32, f32),
+    /// Shows exactly this width, with the height following the window's aspect ratio.
     FixedWidth(f32),
+    /// Shows exactly this height, with the width following the window's aspect ratio.
     FixedHeight(f32),
+    /// Keeps the shorter side of the window at its size, so at least this area is visible when the
+    /// window is roughly square.
     FixedMinimum(f32, f32),
+    /// Shows as much of this area as possible while filling the window, cropping whichever side
+    /// doesn't fit.
+    FixedMaximum(f32, f32),
+    /// One unit is one physical pixel.
+    Pixels,
+    /// One unit is a whole number of physical pixels, as large as possible while still fitting
+    /// `virtual_size` in the window. Anything around the virtual area is still visible unless the
+    /// viewport is letterboxed.
+    PixelPerfect { virtual_size: UVec2 },
+}
+
+impl Default for ProjectionMode {
+    fn default() -> Self {
+        Self::Absolute(2.0, 2.0)
+    }
+}
+
+/// An orthographic projection from the world to the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub mode: ProjectionMode,
+    /// The closest depth that is drawn.
+    pub near: f32,
+    /// The furthest depth that is drawn.
+    pub far: f32,
 }
 
 impl Projection {
-    #[must_use] pub fn as_mat4(self, screen_size: PhysicalSize<u32>) -> Mat4 {
+    pub const DEFAULT_NEAR: f32 = -10.0;
+    pub const DEFAULT_FAR: f32 = 10.0;
+
+    #[must_use]
+    pub fn new(mode: ProjectionMode) -> Self {
+        Self {
+            mode,
+            near: Self::DEFAULT_NEAR,
+            far: Self::DEFAULT_FAR,
+        }
+    }
+
+    #[must_use]
+    pub fn with_depth(self, near: f32, far: f32) -> Self {
+        Self { near, far, ..self }
+    }
+
+    /// The size of the area of the world that is visible on a screen, ignoring camera zoom.
+    #[must_use]
+    pub fn visible_size(self, screen_size: PhysicalSize<u32>) -> Vec2 {
         let window_size = vec2(screen_size.width as f32, screen_size.height as f32);
-        match self {
-            Projection::Absolute(width, height) => Mat4::orthographic_rh(
-                -width / 2.0,
-                width / 2.0,
-                -height / 2.0,
-                height / 2.0,
-                -10.0,
-                10.0,
-            ),
-            Projection::FixedWidth(width) => Mat4::orthographic_rh(
-                -width / 2.0,
-                width / 2.0,
-                -width * (window_size.y / window_size.x) / 2.0,
-                width * (window_size.y / window_size.x) / 2.0,
-                -10.0,
-                10.0,
-            ),
-            Projection::FixedHeight(height) => Mat4::orthographic_rh(
-                -height * (window_size.x / window_size.y) / 2.0,
-                height * (window_size.x / window_size.y) / 2.0,
-                -height / 2.0,
-                height / 2.0,
-                -10.0,
-                10.0,
-            ),
-            Projection::FixedMinimum(width, height) => {
+        match self.mode {
+            ProjectionMode::Absolute(width, height) => vec2(width, height),
+            ProjectionMode::FixedWidth(width) => {
+                vec2(width, width * (window_size.y / window_size.x))
+            }
+            ProjectionMode::FixedHeight(height) => {
+                vec2(height * (window_size.x / window_size.y), height)
+            }
+            ProjectionMode::FixedMinimum(width, height) => {
                 if window_size.x > window_size.y {
-                    Self::as_mat4(Projection::FixedHeight(height), screen_size)
+                    Self::new(ProjectionMode::FixedHeight(height)).visible_size(screen_size)
                 } else {
-                    Self::as_mat4(Projection::FixedWidth(width), screen_size)
+                    Self::new(ProjectionMode::FixedWidth(width)).visible_size(screen_size)
                 }
             }
+            ProjectionMode::FixedMaximum(width, height) => {
+                if window_size.x / window_size.y > width / height {
+                    Self::new(ProjectionMode::FixedWidth(width)).visible_size(screen_size)
+                } else {
+                    Self::new(ProjectionMode::FixedHeight(height)).visible_size(screen_size)
+                }
+            }
+            ProjectionMode::Pixels => window_size,
+            ProjectionMode::PixelPerfect { virtual_size } => {
+                window_size / Self::pixel_scale(virtual_size, screen_size)
+            }
+        }
+    }
+
+    /// The size in physical pixels of the largest area of the screen that shows the projection's
+    /// fixed area without stretching or extra space, or `None` if the mode has no fixed area.
+    #[must_use]
+    pub fn fixed_area(self, screen_size: PhysicalSize<u32>) -> Option<Vec2> {
+        let window_size = vec2(screen_size.width as f32, screen_size.height as f32);
+        match self.mode {
+            ProjectionMode::Absolute(width, height)
+            | ProjectionMode::FixedMinimum(width, height)
+            | ProjectionMode::FixedMaximum(width, height) => {
+                let scale = (window_size / vec2(width, height)).min_element();
+                Some((vec2(width, height) * scale).min(window_size))
+            }
+            ProjectionMode::PixelPerfect { virtual_size } => Some(
+                (virtual_size.as_vec2() * Self::pixel_scale(virtual_size, screen_size))
+                    .min(window_size),
+            ),
+            ProjectionMode::FixedWidth(_)
+            | ProjectionMode::FixedHeight(_)
+            | ProjectionMode::Pixels => None,
         }
     }
+
+    /// How many physical pixels make up one unit of a [`ProjectionMode::PixelPerfect`] projection.
+    #[must_use]
+    pub fn pixel_scale(virtual_size: UVec2, screen_size: PhysicalSize<u32>) -> f32 {
+        (uvec2(screen_size.width, screen_size.height) / virtual_size.max(UVec2::ONE))
+            .min_element()
+            .max(1) as f32
+    }
+
+    #[must_use] pub fn as_mat4(self, screen_size: PhysicalSize<u32>) -> Mat4 {
+        let (min, max) = match self.mode {
+            // Put the origin on a whole pixel, otherwise odd sizes put texel edges halfway
+            // through pixels
+            ProjectionMode::Pixels | ProjectionMode::PixelPerfect { .. } => {
+                let size = uvec2(screen_size.width, screen_size.height);
+                let scale = self.visible_size(screen_size) / size.max(UVec2::ONE).as_vec2();
+                let below = (size / 2).as_vec2();
+                (-below * scale, (size.as_vec2() - below) * scale)
+            }
+            _ => {
+                let half = self.visible_size(screen_size) / 2.0;
+                (-half, half)
+            }
+        };
+        Mat4::orthographic_rh(min.x, max.x, min.y, max.y, self.near, self.far)
+    }
 }
 
 impl Default for Projection {
     fn default() -> Self {
-        Self::Absolute(2.0, 2.0)
+        Self::new(ProjectionMode::default())
+    }
+}
+
+impl From<ProjectionMode> for Projection {
+    fn from(value: ProjectionMode) -> Self {
+        Self::new(value)
     }
 }
+
+#[cfg(test)]
+fn assert_projects(projection: Projection, screen_size: (u32, u32), point: Vec3, expected: Vec3) {
+    let actual = projection
+        .as_mat4(PhysicalSize::new(screen_size.0, screen_size.1))
+        .project_point3(point);
+    assert!(
+        actual.abs_diff_eq(expected, 1e-5),
+        "{projection:?} projected {point} to {actual} instead of {expected}"
+    );
+}
+
+#[test]
+fn absolute_projection_test() {
+    let projection = ProjectionMode::Absolute(4.0, 2.0).into();
+    assert_projects(
+        projection,
+        (800, 600),
+        Vec3::new(2.0, 1.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+    assert_projects(
+        projection,
+        (100, 900),
+        Vec3::new(-1.0, 0.0, 0.0),
+        Vec3::new(-0.5, 0.0, 0.5),
+    );
+}
+
+#[test]
+fn fixed_width_projection_test() {
+    let projection = ProjectionMode::FixedWidth(2.0).into();
+    assert_projects(
+        projection,
+        (800, 600),
+        Vec3::new(1.0, 0.75, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+}
+
+#[test]
+fn fixed_height_projection_test() {
+    let projection = ProjectionMode::FixedHeight(2.0).into();
+    assert_projects(
+        projection,
+        (800, 400),
+        Vec3::new(2.0, 1.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+}
+
+#[test]
+fn fixed_minimum_projection_test() {
+    let projection = ProjectionMode::FixedMinimum(10.0, 10.0).into();
+    assert_projects(
+        projection,
+        (800, 400),
+        Vec3::new(10.0, 5.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+    assert_projects(
+        projection,
+        (400, 800),
+        Vec3::new(5.0, 10.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+}
+
+#[test]
+fn fixed_maximum_projection_test() {
+    let projection = ProjectionMode::FixedMaximum(10.0, 10.0).into();
+    assert_projects(
+        projection,
+        (800, 400),
+        Vec3::new(5.0, 2.5, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+    assert_projects(
+        projection,
+        (400, 800),
+        Vec3::new(2.5, 5.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+}
+
+#[test]
+fn pixels_projection_test() {
+    let projection = ProjectionMode::Pixels.into();
+    assert_projects(
+        projection,
+        (800, 600),
+        Vec3::new(400.0, -300.0, 0.0),
+        Vec3::new(1.0, -1.0, 0.5),
+    );
+}
+
+#[test]
+fn pixel_perfect_projection_test() {
+    let projection = ProjectionMode::PixelPerfect {
+        virtual_size: uvec2(320, 180),
+    }
+    .into();
+    // 1000 / 320 and 600 / 180 both floor to 3 times scaling
+    assert_projects(
+        projection,
+        (1000, 600),
+        Vec3::new(160.0, 90.0, 0.0),
+        Vec3::new(0.96, 0.9, 0.5),
+    );
+    // Too small to fit, so stays at 1 times scaling
+    assert_projects(
+        projection,
+        (200, 100),
+        Vec3::new(100.0, 50.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.5),
+    );
+}
+
+#[test]
+fn odd_size_pixel_projection_test() {
+    // The origin is on the boundary between the 400th and 401st pixel rather than in the middle
+    // of a pixel
+    assert_projects(
+        ProjectionMode::Pixels.into(),
+        (801, 601),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(800.0 / 801.0 - 1.0, 600.0 / 601.0 - 1.0, 0.5),
+    );
+    let projection = ProjectionMode::PixelPerfect {
+        virtual_size: uvec2(320, 180),
+    }
+    .into();
+    assert_projects(
+        projection,
+        (1001, 601),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1000.0 / 1001.0 - 1.0, 600.0 / 601.0 - 1.0, 0.5),
+    );
+    // Every unit is 3 whole pixels
+    assert_projects(
+        projection,
+        (1001, 601),
+        Vec3::new(1.0, -1.0, 0.0),
+        Vec3::new(1006.0 / 1001.0 - 1.0, 594.0 / 601.0 - 1.0, 0.5),
+    );
+}
+
+#[test]
+fn fixed_area_test() {
+    let size = PhysicalSize::new(800, 400);
+    assert_eq!(
+        Projection::new(ProjectionMode::FixedMinimum(10.0, 10.0)).fixed_area(size),
+        Some(Vec2::new(400.0, 400.0))
+    );
+    assert_eq!(
+        Projection::new(ProjectionMode::PixelPerfect {
+            virtual_size: uvec2(320, 180)
+        })
+        .fixed_area(size),
+        Some(Vec2::new(640.0, 360.0))
+    );
+    assert_eq!(
+        Projection::new(ProjectionMode::FixedWidth(10.0)).fixed_area(size),
+        None
+    );
+}
+
+#[test]
+fn projection_depth_test() {
+    let projection = Projection::new(ProjectionMode::Absolute(2.0, 2.0)).with_depth(0.0, 100.0);
+    assert_projects(
+        projection,
+        (800, 600),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 0.0),
+    );
+    assert_projects(
+        projection,
+        (800, 600),
+        Vec3::new(0.0, 0.0, -100.0),
+        Vec3::new(0.0, 0.0, 1.0),
+    );
+}
@@ -0,0 +1,245 @@
+//! Hierarchies of transforms, where children move with their parents.
+
+use crate::{
+    handle::{impl_handle, HandleSlab},
+    renderer::Renderer,
+    sprite::SpriteHandle,
+    transform::Transform,
+};
+use glam::{Mat4, Vec3};
+
+/// A reference to a node in a [`Scene`], which stops referring to anything once the node is
+/// removed, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+impl_handle!(NodeId);
+
+/// A transform that is relative to its parent.
+#[derive(Clone, Debug)]
+pub struct Node {
+    /// The transform of the node relative to its parent.
+    pub transform: Transform,
+    /// A sprite and the index of the instance of it that follows this node.
+    pub sprite: Option<(SpriteHandle, usize)>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Mat4,
+}
+
+impl Node {
+    #[must_use]
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    #[must_use]
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// The matrix from the node's local space to world space, as of the last [`Scene::update`].
+    #[must_use]
+    pub fn world_matrix(&self) -> Mat4 {
+        self.world
+    }
+}
+
+/// A tree of [`Node`]s whose world transforms are calculated once per frame.
+#[derive(Debug, Default)]
+pub struct Scene {
+    nodes: HandleSlab<NodeId, Node>,
+}
+
+impl Scene {
+    /// Adds a node without a parent.
+    pub fn insert(&mut self, transform: Transform) -> NodeId {
+        self.nodes.insert(Node {
+            transform,
+            sprite: None,
+            parent: None,
+            children: Vec::new(),
+            world: Mat4::from(&transform),
+        })
+    }
+
+    /// Adds a node as the child of `parent`.
+    ///
+    /// # Panics
+    /// If `parent` has been removed.
+    pub fn insert_child(&mut self, parent: NodeId, transform: Transform) -> NodeId {
+        assert!(self.contains(parent), "parent node has been removed");
+        let id = self.insert(transform);
+        self.set_parent(id, Some(parent));
+        id
+    }
+
+    /// Removes a node and all of its descendants.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        self.set_parent(id, None);
+        let node = self.get(id)?.clone();
+        for child in &node.children {
+            self.remove(*child);
+        }
+        self.nodes.remove(id)
+    }
+
+    #[must_use]
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    #[must_use]
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.nodes.get_mut(id)
+    }
+
+    #[must_use]
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains(id)
+    }
+
+    /// Moves a node to a new parent, or makes it a root if `parent` is `None`. The node keeps its
+    /// local transform, so it will move in world space. Returns `false` and does nothing if either
+    /// node has been removed or if `parent` is a descendant of the node.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        if let Some(parent) = parent {
+            let mut ancestor = Some(parent);
+            while let Some(node) = ancestor {
+                if node == id {
+                    return false;
+                }
+                match self.get(node) {
+                    Some(node) => ancestor = node.parent,
+                    None => return false,
+                }
+            }
+        }
+
+        if let Some(old) = self.get(id).and_then(|node| node.parent) {
+            if let Some(old) = self.get_mut(old) {
+                old.children.retain(|child| *child != id);
+            }
+        }
+        if let Some(parent) = parent.and_then(|parent| self.get_mut(parent)) {
+            parent.children.push(id);
+        }
+        self.get_mut(id).unwrap().parent = parent;
+        true
+    }
+
+    /// Calculates the world matrix of every node and writes them to the sprites that follow them.
+    pub fn update(&mut self, renderer: &mut Renderer) {
+        self.update_transforms();
+        for (_, node) in self.nodes.iter() {
+            let Some((handle, instance)) = node.sprite else {
+                continue;
+            };
+            let world = matrix_to_transform(node.world);
+            if let Some(sprite) = renderer.get_mut(handle) {
+                if sprite.transforms.get(instance) != Some(&world) {
+                    if let Some(transform) = sprite.transforms.get_mut(instance) {
+                        *transform = world;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calculates the world matrix of every node without touching any sprites.
+    pub fn update_transforms(&mut self) {
+        let mut stack: Vec<_> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.parent.is_none())
+            .map(|(id, _)| (id, Mat4::IDENTITY))
+            .collect();
+
+        while let Some((id, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get_mut(id) else {
+                continue;
+            };
+            node.world = parent * Mat4::from(&node.transform);
+            stack.extend(node.children.iter().map(|child| (*child, node.world)));
+        }
+    }
+
+    /// The world transform of a node, as of the last [`Scene::update`].
+    #[must_use]
+    pub fn world_transform(&self, id: NodeId) -> Option<Transform> {
+        Some(matrix_to_transform(self.get(id)?.world))
+    }
+
+    /// Converts a point in a node's local space to world space.
+    #[must_use]
+    pub fn local_to_world(&self, id: NodeId, point: Vec3) -> Option<Vec3> {
+        Some(self.get(id)?.world.transform_point3(point))
+    }
+
+    /// Converts a point in world space to a node's local space.
+    #[must_use]
+    pub fn world_to_local(&self, id: NodeId, point: Vec3) -> Option<Vec3> {
+        Some(self.get(id)?.world.inverse().transform_point3(point))
+    }
+}
+
+fn matrix_to_transform(matrix: Mat4) -> Transform {
+    let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+    Transform {
+        translation,
+        rotation,
+        scale,
+    }
+}
+
+#[test]
+fn scene_test() {
+    use glam::{Quat, Vec2};
+
+    let mut scene = Scene::default();
+    let root = scene.insert(Transform::translation(Vec3::X).with_scale(Vec3::splat(2.0)));
+    let child = scene.insert_child(root, Transform::rotation(Quat::from_rotation_z(1.0)));
+    let grandchild = scene.insert_child(child, Transform::translation(Vec3::Y));
+    let other = scene.insert(Transform::default());
+
+    // Parenting a node to itself or one of its descendants would make a cycle
+    assert!(!scene.set_parent(root, Some(grandchild)));
+    assert!(!scene.set_parent(child, Some(child)));
+    assert_eq!(scene.get(root).unwrap().parent(), None);
+    assert!(scene.set_parent(other, Some(grandchild)));
+    assert!(scene.set_parent(other, None));
+    assert_eq!(scene.get(grandchild).unwrap().children(), []);
+
+    scene.update_transforms();
+    let rotated = Vec2::from_angle(1.0).rotate(Vec2::Y) * 2.0;
+    let expected = Vec3::X + rotated.extend(0.0);
+    assert!(scene
+        .local_to_world(grandchild, Vec3::ZERO)
+        .unwrap()
+        .abs_diff_eq(expected, 1e-5));
+    assert!(scene
+        .world_to_local(grandchild, expected)
+        .unwrap()
+        .abs_diff_eq(Vec3::ZERO, 1e-5));
+
+    let world = scene.world_transform(grandchild).unwrap();
+    assert!(world.translation.abs_diff_eq(expected, 1e-5));
+    assert!(world.scale.abs_diff_eq(Vec3::splat(2.0), 1e-5));
+    assert!(world.rotation.abs_diff_eq(Quat::from_rotation_z(1.0), 1e-5));
+    assert!(Mat4::from(&world).abs_diff_eq(scene.get(grandchild).unwrap().world_matrix(), 1e-5));
+
+    // Removing a node removes its whole subtree
+    assert!(scene.remove(child).is_some());
+    assert!(!scene.contains(child) && !scene.contains(grandchild));
+    assert_eq!(scene.get(root).unwrap().children(), []);
+    assert!(scene.remove(grandchild).is_none());
+    let reused = scene.insert(Transform::default());
+    assert!(!scene.contains(child) && !scene.contains(grandchild));
+    assert!(scene.contains(reused) && scene.contains(other));
+}
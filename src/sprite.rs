@@ -1,4 +1,4 @@
-use std::mem::size_of;
+use std::{mem::size_of, sync::Arc};
 
 use dirtytype::Dirty;
 use wgpu::{
@@ -9,24 +9,87 @@ use wgpu::{
 pub use glam::*;
 pub use wgpu::Color;
 
-use crate::{renderer::RendererGlobals, texture::Texture, transform::Transform};
+pub use crate::geometry::Rect;
+
+use crate::{
+    renderer::RendererGlobals,
+    texture::{Texture, TextureHandle},
+    transform::Transform,
+};
 
 pub enum SpriteType {
     Color,
     Texture(wgpu::BindGroup),
 }
 
+/// The coordinate space a sprite's transforms are in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Space {
+    /// Moves with the camera and is scaled by the projection.
+    #[default]
+    World,
+    /// Physical pixels of the viewport with `(0, 0)` at the bottom left, ignoring the camera and
+    /// projection. Useful for text and HUDs.
+    Screen,
+}
+
+/// A reference to a sprite in the [`Renderer`](crate::renderer::Renderer), which stops referring
+/// to anything once the sprite is removed, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpriteHandle {
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+}
+
+crate::handle::impl_handle!(SpriteHandle);
+
 pub trait Vertex: bytemuck::Pod + bytemuck::Zeroable {}
 
+/// The per instance data of a sprite in its instance buffer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct Instance {
+    matrix: Mat4,
+    tint: [f32; 4],
+}
+
+impl Instance {
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            0 => Float32x4,
+            1 => Float32x4,
+            2 => Float32x4,
+            3 => Float32x4,
+            6 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as u64,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
 pub struct Sprite {
     pub(crate) vertex_buffer: wgpu::Buffer,
     pub(crate) index_buffer: wgpu::Buffer,
     pub(crate) index_count: u32,
     pub(crate) ty: SpriteType,
+    /// Whether the texture is single channel, so is drawn as a mask of the tint.
+    pub(crate) mask: bool,
     pub visible: bool,
+    /// Bits that decide which [`Viewport`](crate::viewport::Viewport)s draw the sprite.
+    pub layers: u32,
+    pub space: Space,
     pub transforms: Dirty<Vec<Transform>>,
+    tint: Dirty<Color>,
     pub(crate) transform_buffer: wgpu::Buffer,
     pub(crate) transform_count: u32,
+    nine_slice: Option<NineSlice>,
+    /// The texture and sampler the bind group was made from, and the version of the texture at
+    /// the time, so it can be rebuilt when the texture is reloaded.
+    texture: Option<(Arc<Texture>, Arc<wgpu::Sampler>, u32)>,
+    pending_texture: Option<(TextureHandle, Arc<wgpu::Sampler>)>,
 }
 
 impl Sprite {
@@ -34,7 +97,7 @@ impl Sprite {
     pub fn new_polygon(
         vertices: &[impl Vertex],
         indices: &[u16],
-        texture: Option<(&wgpu::TextureView, &wgpu::Sampler)>,
+        texture: Option<(&Arc<Texture>, &Arc<wgpu::Sampler>)>,
         transforms: Vec<Transform>,
     ) -> Self {
         Self {
@@ -53,39 +116,30 @@ impl Sprite {
                     usage: wgpu::BufferUsages::INDEX,
                 }),
             index_count: indices.len() as u32,
+            mask: texture.is_some_and(|(texture, _)| texture.is_mask()),
             ty: match texture {
-                Some((view, sampler)) => {
-                    SpriteType::Texture(RendererGlobals::get().device.create_bind_group(
-                        &wgpu::BindGroupDescriptor {
-                            label: None,
-                            layout: &Self::texture_bind_group_layout(),
-                            entries: &[
-                                wgpu::BindGroupEntry {
-                                    binding: 0,
-                                    resource: wgpu::BindingResource::TextureView(view),
-                                },
-                                wgpu::BindGroupEntry {
-                                    binding: 1,
-                                    resource: wgpu::BindingResource::Sampler(sampler),
-                                },
-                            ],
-                        },
-                    ))
+                Some((texture, sampler)) => {
+                    SpriteType::Texture(Self::texture_bind_group(texture, sampler))
                 }
                 None => SpriteType::Color,
             },
             transform_buffer: RendererGlobals::get().device.create_buffer_init(
                 &BufferInitDescriptor {
                     label: None,
-                    contents: bytemuck::cast_slice(
-                        &transforms.iter().map(Mat4::from).collect::<Vec<_>>(),
-                    ),
+                    contents: bytemuck::cast_slice(&Self::instances(&transforms, Color::WHITE)),
                     usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
                 },
             ),
             transform_count: transforms.len() as u32,
             visible: true,
+            layers: 1,
+            space: Space::World,
             transforms: Dirty::new(transforms),
+            tint: Dirty::new(Color::WHITE),
+            nine_slice: None,
+            texture: texture
+                .map(|(texture, sampler)| (texture.clone(), sampler.clone(), texture.version())),
+            pending_texture: None,
         }
     }
 
@@ -96,23 +150,164 @@ impl Sprite {
 
     #[must_use]
     pub fn new_texture(
-        texture: &Texture,
-        sampler: &wgpu::Sampler,
+        texture: &Arc<Texture>,
+        sampler: &Arc<wgpu::Sampler>,
         source: Option<Rect>,
         transform: Vec<Transform>,
     ) -> Self {
         Self::new_polygon(
             &TextureVertex::quad(source),
             &Self::INDICES,
-            Some((&texture.view, sampler)),
+            Some((texture, sampler)),
             transform,
         )
     }
 
+    /// Creates a sprite that draws the placeholder of a texture that is loading in the background,
+    /// and switches to the texture once it has loaded.
+    #[must_use]
+    pub fn new_texture_handle(
+        handle: &TextureHandle,
+        sampler: &Arc<wgpu::Sampler>,
+        source: Option<Rect>,
+        transforms: Vec<Transform>,
+    ) -> Self {
+        Self {
+            pending_texture: (!handle.is_done()).then(|| (handle.clone(), sampler.clone())),
+            ..Self::new_texture(handle.texture(), sampler, source, transforms)
+        }
+    }
+
+    /// Creates a sprite that stretches the middle of a texture to fill `size` while keeping the
+    /// borders described by `insets` the same size. `size` is in the same units as the insets, so
+    /// use a [`Transform`] scale to convert it to world units. If `size` is smaller than the
+    /// insets on an axis, the borders on that axis shrink proportionally until they meet, and a
+    /// negative size is treated as zero.
+    #[must_use]
+    pub fn new_nine_slice(
+        texture: &Arc<Texture>,
+        sampler: &Arc<wgpu::Sampler>,
+        insets: Insets,
+        size: Vec2,
+        transforms: Vec<Transform>,
+    ) -> Self {
+        let nine_slice = NineSlice {
+            texture_size: texture.size().as_vec2(),
+            insets,
+            size,
+        };
+        Self {
+            nine_slice: Some(nine_slice),
+            ..Self::new_polygon(
+                &nine_slice.vertices(),
+                &NineSlice::INDICES,
+                Some((texture, sampler)),
+                transforms,
+            )
+        }
+    }
+
+    /// The size of a sprite made with [`Sprite::new_nine_slice`].
+    #[must_use]
+    pub fn nine_slice_size(&self) -> Option<Vec2> {
+        self.nine_slice.map(|nine_slice| nine_slice.size)
+    }
+
+    /// Changes the size of a sprite made with [`Sprite::new_nine_slice`], does nothing for other
+    /// sprites.
+    pub fn set_nine_slice_size(&mut self, size: Vec2) {
+        if let Some(nine_slice) = &mut self.nine_slice {
+            if nine_slice.size != size {
+                nine_slice.size = size;
+                let vertices = nine_slice.vertices();
+                self.set_vertices(&vertices);
+            }
+        }
+    }
+
+    /// The color that the sprite's colors are multiplied by.
+    #[must_use]
+    pub fn tint(&self) -> Color {
+        *self.tint
+    }
+
+    /// Multiplies the colors of the sprite, including its texture, by a color. White leaves the
+    /// sprite unchanged.
+    pub fn set_tint(&mut self, tint: Color) {
+        if *self.tint != tint {
+            *self.tint = tint;
+        }
+    }
+
+    fn instances(transforms: &[Transform], tint: Color) -> Vec<Instance> {
+        let tint = [tint.r, tint.g, tint.b, tint.a].map(|x| x as f32);
+        transforms
+            .iter()
+            .map(|transform| Instance {
+                matrix: transform.into(),
+                tint,
+            })
+            .collect()
+    }
+
+    /// Writes the transforms and tint to the instance buffer if either has changed.
+    pub(crate) fn upload_instances(&mut self) {
+        if self.transforms.dirty || self.tint.dirty {
+            RendererGlobals::get().queue.write_buffer(
+                &self.transform_buffer,
+                0,
+                bytemuck::cast_slice(&Self::instances(&self.transforms, *self.tint)),
+            );
+            self.transforms.dirty = false;
+            self.tint.dirty = false;
+        }
+    }
+
+    /// Changes the texture of a textured sprite, does nothing for color sprites.
+    pub fn set_texture(&mut self, texture: &Arc<Texture>, sampler: &Arc<wgpu::Sampler>) {
+        if let SpriteType::Texture(bind_group) = &mut self.ty {
+            *bind_group = Self::texture_bind_group(texture, sampler);
+            self.mask = texture.is_mask();
+            self.texture = Some((texture.clone(), sampler.clone(), texture.version()));
+        }
+        self.pending_texture = None;
+    }
+
+    /// Switches to the texture of a [`Sprite::new_texture_handle`] once it has loaded, and
+    /// rebinds textures that have been reloaded.
+    pub(crate) fn update_texture(&mut self) {
+        if let Some((handle, sampler)) = self.pending_texture.take() {
+            if handle.is_done() {
+                self.set_texture(handle.texture(), &sampler);
+            } else {
+                self.pending_texture = Some((handle, sampler));
+            }
+        }
+        if let (Some((texture, sampler, version)), SpriteType::Texture(bind_group)) =
+            (&mut self.texture, &mut self.ty)
+        {
+            if texture.version() != *version {
+                *bind_group = Self::texture_bind_group(texture, sampler);
+                *version = texture.version();
+                self.mask = texture.is_mask();
+            }
+        }
+    }
+
     pub fn with_visibility(self, visible: bool) -> Self {
         Self { visible, ..self }
     }
 
+    #[must_use]
+    pub fn with_layers(self, layers: u32) -> Self {
+        Self { layers, ..self }
+    }
+
+    #[must_use]
+    pub fn with_space(self, space: Space) -> Self {
+        Self { space, ..self }
+    }
+
     pub fn set_vertices(&mut self, vertices: &[impl Vertex]) {
         RendererGlobals::get().queue.write_buffer(
             &self.vertex_buffer,
@@ -121,11 +316,18 @@ impl Sprite {
         );
     }
 
+    /// The built in shader that sprites are drawn with.
+    pub(crate) fn shader() -> wgpu::ShaderModule {
+        RendererGlobals::get()
+            .device
+            .create_shader_module(include_wgsl!("color.wgsl"))
+    }
+
     pub(crate) fn color_pipeline(
         projection_layout: &wgpu::BindGroupLayout,
+        shader: &wgpu::ShaderModule,
     ) -> wgpu::RenderPipeline {
         let device = &RendererGlobals::get().device;
-        let shader = device.create_shader_module(include_wgsl!("color.wgsl"));
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[projection_layout],
@@ -135,9 +337,9 @@ impl Sprite {
             label: Some("color pipeline"),
             layout: Some(&layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: "color_vertex",
-                buffers: &[ColorVertex::desc(), Transform::desc()],
+                buffers: &[ColorVertex::desc(), Instance::desc()],
             },
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
@@ -161,7 +363,7 @@ impl Sprite {
                 alpha_to_coverage_enabled: false,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: "color_fragment",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: wgpu::TextureFormat::Bgra8UnormSrgb,
@@ -173,23 +375,29 @@ impl Sprite {
         })
     }
 
+    /// The pipeline for textured sprites, or for single channel textures if `mask` is set.
     pub(crate) fn texture_pipeline(
         projection_layout: &wgpu::BindGroupLayout,
+        shader: &wgpu::ShaderModule,
+        mask: bool,
     ) -> wgpu::RenderPipeline {
         let device = &RendererGlobals::get().device;
-        let shader = device.create_shader_module(include_wgsl!("color.wgsl"));
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
             bind_group_layouts: &[projection_layout, &Self::texture_bind_group_layout()],
             push_constant_ranges: &[],
         });
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("texture pipeline"),
+            label: Some(if mask {
+                "mask pipeline"
+            } else {
+                "texture pipeline"
+            }),
             layout: Some(&layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: "texture_vertex",
-                buffers: &[TextureVertex::desc(), Transform::desc()],
+                buffers: &[TextureVertex::desc(), Instance::desc()],
             },
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleList,
@@ -213,8 +421,12 @@ impl Sprite {
                 alpha_to_coverage_enabled: false,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "texture_fragment",
+                module: shader,
+                entry_point: if mask {
+                    "mask_fragment"
+                } else {
+                    "texture_fragment"
+                },
                 targets: &[Some(wgpu::ColorTargetState {
                     format: wgpu::TextureFormat::Bgra8UnormSrgb,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
@@ -225,6 +437,26 @@ impl Sprite {
         })
     }
 
+    fn texture_bind_group(texture: &Texture, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+        let texture = texture.inner();
+        RendererGlobals::get()
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &Self::texture_bind_group_layout(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+    }
+
     fn texture_bind_group_layout() -> wgpu::BindGroupLayout {
         RendererGlobals::get()
             .device
@@ -340,50 +572,142 @@ impl TextureVertex {
 
 impl Vertex for TextureVertex {}
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Rect {
-    pub pos: Vec2,
-    pub size: Vec2,
+/// The border sizes of a nine slice texture, in pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
 }
 
-impl Rect {
-    #[must_use] pub fn contains(&self, point: Vec2) -> bool {
-        point.x >= self.pos.x
-            && point.x < self.pos.x + self.size.x
-            && point.y >= self.pos.y
-            && point.y < self.pos.y + self.size.y
+impl Insets {
+    /// Insets that are the same on every side.
+    #[must_use]
+    pub fn uniform(inset: f32) -> Self {
+        Self {
+            left: inset,
+            right: inset,
+            top: inset,
+            bottom: inset,
+        }
     }
+}
 
-    #[must_use] pub fn overlaps(&self, other: &Self) -> bool {
-        self.pos.x < other.pos.x + other.size.x
-            && self.pos.x + self.size.x > other.pos.x
-            && self.pos.y < other.pos.y + other.size.y
-            && self.pos.y + self.size.y > other.pos.y
-    }
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NineSlice {
+    texture_size: Vec2,
+    insets: Insets,
+    size: Vec2,
 }
 
-#[test]
-fn rect_test() {
-    assert!(Rect {
-        pos: vec2(1.0, 1.0),
-        size: vec2(2.0, 2.0)
-    }
-    .contains(vec2(1.0, 1.05)));
-    assert!(Rect {
-        pos: vec2(1.0, 1.0),
-        size: vec2(2.0, 2.0)
+impl NineSlice {
+    const INDICES: [u16; 54] = {
+        let mut indices = [0; 54];
+        let mut cell = 0;
+        while cell < 9 {
+            let corner = (cell / 3 * 4 + cell % 3) as u16;
+            let quad = [
+                corner,
+                corner + 1,
+                corner + 5,
+                corner,
+                corner + 5,
+                corner + 4,
+            ];
+            let mut i = 0;
+            while i < 6 {
+                indices[cell * 6 + i] = quad[i];
+                i += 1;
+            }
+            cell += 1;
+        }
+        indices
+    };
+
+    fn vertices(&self) -> [TextureVertex; 16] {
+        let size = self.size.max(Vec2::ZERO);
+        // Shrink the borders if the size is too small to fit them
+        let borders = vec2(
+            self.insets.left + self.insets.right,
+            self.insets.top + self.insets.bottom,
+        );
+        let scale = vec2(
+            if borders.x > size.x {
+                size.x / borders.x
+            } else {
+                1.0
+            },
+            if borders.y > size.y {
+                size.y / borders.y
+            } else {
+                1.0
+            },
+        );
+        let left = self.insets.left * scale.x;
+        let right = self.insets.right * scale.x;
+        let top = self.insets.top * scale.y;
+        let bottom = self.insets.bottom * scale.y;
+
+        let half = size / 2.0;
+        let xs = [-half.x, -half.x + left, half.x - right, half.x];
+        let ys = [-half.y, -half.y + bottom, half.y - top, half.y];
+        let us = [
+            0.0,
+            self.insets.left / self.texture_size.x,
+            1.0 - self.insets.right / self.texture_size.x,
+            1.0,
+        ];
+        let vs = [
+            1.0,
+            1.0 - self.insets.bottom / self.texture_size.y,
+            self.insets.top / self.texture_size.y,
+            0.0,
+        ];
+
+        std::array::from_fn(|i| TextureVertex {
+            pos: vec3(xs[i % 4], ys[i / 4], 0.0),
+            tex_coords: vec2(us[i % 4], vs[i / 4]),
+        })
     }
-    .overlaps(&Rect {
-        pos: vec2(1.0, 1.0),
-        size: vec2(2.0, 2.0)
-    }));
 }
 
-impl Default for Rect {
-    fn default() -> Self {
-        Self {
-            pos: Vec2::ZERO,
-            size: Vec2::ONE,
-        }
+#[test]
+fn nine_slice_test() {
+    let mut nine_slice = NineSlice {
+        texture_size: vec2(10.0, 20.0),
+        insets: Insets {
+            left: 1.0,
+            right: 2.0,
+            top: 4.0,
+            bottom: 5.0,
+        },
+        size: vec2(20.0, 30.0),
+    };
+    let vertices = nine_slice.vertices();
+    let xs = [-10.0, -9.0, 8.0, 10.0];
+    let ys = [-15.0, -10.0, 11.0, 15.0];
+    let us = [0.0, 0.1, 0.8, 1.0];
+    let vs = [1.0, 0.75, 0.2, 0.0];
+    for (i, vertex) in vertices.iter().enumerate() {
+        assert_eq!(vertex.pos, vec3(xs[i % 4], ys[i / 4], 0.0));
+        assert!(vertex
+            .tex_coords
+            .abs_diff_eq(vec2(us[i % 4], vs[i / 4]), 1e-6));
     }
+    // Every cell is two triangles between its corners
+    assert_eq!(NineSlice::INDICES[..6], [0, 1, 5, 0, 5, 4]);
+    assert_eq!(NineSlice::INDICES[48..], [10, 11, 15, 10, 15, 14]);
+    assert!(NineSlice::INDICES.iter().all(|&index| index < 16));
+
+    // Too small to fit the borders, so they meet in the middle
+    nine_slice.size = vec2(1.5, -1.0);
+    let vertices = nine_slice.vertices();
+    assert_eq!(
+        vertices[..4].iter().map(|v| v.pos.x).collect::<Vec<_>>(),
+        [-0.75, -0.25, -0.25, 0.75]
+    );
+    assert!(vertices.iter().all(|v| v.pos.y == 0.0));
+    // The texture is still sliced at the insets
+    assert!(vertices[5].tex_coords.abs_diff_eq(vec2(0.1, 0.75), 1e-6));
 }
@@ -1,107 +1,1461 @@
-use std::{borrow::Cow, path::Path, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc, Arc, Mutex, OnceLock, RwLock, RwLockReadGuard,
+    },
+    thread,
+    time::Duration,
+};
 
 use fxhash::FxHashMap;
 use glam::{uvec2, UVec2};
-use image::RgbaImage;
-use wgpu::{util::DeviceExt, TextureView};
+use half::f16;
+use image::{DynamicImage, RgbaImage};
+use wgpu::{include_wgsl, TextureView};
 
-use crate::renderer::RendererGlobals;
+use crate::{renderer::RendererGlobals, watch::FileWatcher};
+
+pub use wgpu::{AddressMode, FilterMode};
 
 #[derive(Debug)]
 pub struct Texture {
+    inner: RwLock<TextureInner>,
+    version: AtomicU32,
+}
+
+#[derive(Debug)]
+pub(crate) struct TextureInner {
+    pub(crate) texture: wgpu::Texture,
     pub(crate) view: TextureView,
-    pub size: UVec2,
 }
 
-impl Texture {
+impl TextureInner {
     fn new(texture: wgpu::Texture) -> Self {
         Self {
             view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
-            size: uvec2(texture.width(), texture.height()),
+            texture,
         }
     }
+
+    fn size(&self) -> UVec2 {
+        uvec2(self.texture.width(), self.texture.height())
+    }
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub enum TextureSource<'a> {
-    Path(&'a Path),
+impl Texture {
+    pub(crate) fn new(texture: wgpu::Texture) -> Self {
+        Self {
+            inner: RwLock::new(TextureInner::new(texture)),
+            version: AtomicU32::new(0),
+        }
+    }
+
+    pub(crate) fn inner(&self) -> RwLockReadGuard<'_, TextureInner> {
+        self.inner.read().unwrap()
+    }
+
+    /// Swaps the GPU texture, such as after a hot reload, so sprites using it rebind.
+    pub(crate) fn replace(&self, texture: wgpu::Texture) {
+        *self.inner.write().unwrap() = TextureInner::new(texture);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn size(&self) -> UVec2 {
+        self.inner().size()
+    }
+
+    /// Whether the texture has a single channel, from [`TextureFormat::R8`].
+    pub(crate) fn is_mask(&self) -> bool {
+        self.inner().texture.format() == wgpu::TextureFormat::R8Unorm
+    }
+
+    /// Increased every time the texture is replaced, such as by a hot reload.
+    #[must_use]
+    pub fn version(&self) -> u32 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// Creates a transparent texture that can be drawn into with [`Texture::write_region`].
+    ///
+    /// # Panics
+    /// If the size is bigger than the device supports.
+    #[must_use]
+    pub fn empty(size: UVec2) -> Self {
+        Self::new(
+            Self::from_image(&RgbaImage::new(size.x, size.y), TextureOptions::default()).unwrap(),
+        )
+    }
+
+    /// Replaces the pixels of the texture starting at `pos`, with the top left being `(0, 0)`.
+    /// Only textures without mipmaps can be written to, since the smaller levels would be left
+    /// showing the old pixels.
+    ///
+    /// # Panics
+    /// If the image doesn't fit inside the texture at `pos`, the texture isn't 8-bit RGBA, or it
+    /// has mipmaps.
+    pub fn write_region(&self, pos: UVec2, image: &RgbaImage) {
+        assert_eq!(
+            self.inner().texture.mip_level_count(),
+            1,
+            "textures with mipmaps can't be written to"
+        );
+        self.write_subimage(
+            pos,
+            image,
+            UVec2::ZERO,
+            uvec2(image.width(), image.height()),
+        );
+    }
+
+    /// Copies the area of `image` at `source` with `size` into the texture at `pos`, without
+    /// copying the pixels on the CPU first.
+    ///
+    /// # Panics
+    /// If the texture isn't 8-bit RGBA.
+    pub(crate) fn write_subimage(&self, pos: UVec2, image: &RgbaImage, source: UVec2, size: UVec2) {
+        let inner = self.inner();
+        assert!(
+            matches!(
+                inner.texture.format(),
+                wgpu::TextureFormat::Rgba8UnormSrgb | wgpu::TextureFormat::Rgba8Unorm
+            ),
+            "only 8-bit RGBA textures can be written to"
+        );
+        assert!(
+            (pos + size).cmple(inner.size()).all()
+                && (source + size)
+                    .cmple(uvec2(image.width(), image.height()))
+                    .all(),
+            "region is outside of the texture"
+        );
+        if size.cmpeq(UVec2::ZERO).any() {
+            return;
+        }
+        RendererGlobals::get().queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &inner.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: pos.x,
+                    y: pos.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            image,
+            wgpu::ImageDataLayout {
+                offset: (source.y as u64 * image.width() as u64 + source.x as u64) * 4,
+                bytes_per_row: Some(image.width() * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// The approximate amount of GPU memory used by the texture, including its mipmaps.
+    #[must_use]
+    pub fn bytes(&self) -> usize {
+        let inner = self.inner();
+        (0..inner.texture.mip_level_count())
+            .map(|level| {
+                let size = (inner.size() >> level).max(UVec2::ONE);
+                size.x as usize
+                    * size.y as usize
+                    * inner.texture.format().block_size(None).unwrap_or(4) as usize
+            })
+            .sum()
+    }
+
+    /// Creates an 8-bit RGBA texture, ignoring the format of the options.
+    pub(crate) fn from_image(
+        image: &RgbaImage,
+        options: TextureOptions,
+    ) -> Result<wgpu::Texture, TextureError> {
+        let format = match options.format {
+            TextureFormat::Linear => TextureFormat::Linear,
+            _ => TextureFormat::Srgb,
+        };
+        Self::create(
+            uvec2(image.width(), image.height()),
+            image.as_raw(),
+            TextureOptions { format, ..options },
+        )
+    }
+
+    /// Converts an image to the format of the options and creates a texture from it.
+    pub(crate) fn from_dynamic(
+        image: DynamicImage,
+        options: TextureOptions,
+    ) -> Result<wgpu::Texture, TextureError> {
+        let size = uvec2(image.width(), image.height());
+        Self::create(size, &options.format.pixels(image), options)
+    }
+
+    fn create(
+        size: UVec2,
+        data: &[u8],
+        options: TextureOptions,
+    ) -> Result<wgpu::Texture, TextureError> {
+        let device = &RendererGlobals::get().device;
+        let max = device.limits().max_texture_dimension_2d;
+        if size.x > max || size.y > max {
+            return Err(TextureError::TooLarge { size, max });
+        }
+
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        let mip_level_count = if options.mipmaps {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+            size.max_element().max(1).ilog2() + 1
+        } else {
+            1
+        };
+        let extent = wgpu::Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: extent,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: options.format.into(),
+            usage,
+            view_formats: &[],
+        });
+        if size.x > 0 && size.y > 0 {
+            RendererGlobals::get().queue.write_texture(
+                texture.as_image_copy(),
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.x * options.format.bytes_per_pixel()),
+                    rows_per_image: None,
+                },
+                extent,
+            );
+        }
+        Ok(texture)
+    }
+}
+
+/// The smallest rectangle of pixels covering every change since the last upload.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DirtyRegion(Option<(UVec2, UVec2)>);
+
+impl DirtyRegion {
+    pub(crate) fn add(&mut self, pos: UVec2, size: UVec2) {
+        let max = pos + size;
+        self.0 = Some(match self.0 {
+            Some((min, old_max)) => (min.min(pos), old_max.max(max)),
+            None => (pos, max),
+        });
+    }
+
+    /// The position and size of the region, clearing it.
+    pub(crate) fn take(&mut self) -> Option<(UVec2, UVec2)> {
+        self.0.take().map(|(min, max)| (min, max - min))
+    }
+}
+
+/// A texture with a copy of its pixels on the CPU, where only the changed area is uploaded,
+/// such as for procedural content, video frames or painting.
+#[derive(Debug)]
+pub struct DynamicTexture {
+    image: RgbaImage,
+    texture: Arc<Texture>,
+    dirty: DirtyRegion,
+}
+
+impl DynamicTexture {
+    /// Creates a transparent texture.
+    ///
+    /// # Panics
+    /// If the size is bigger than the device supports.
+    #[must_use]
+    pub fn new(size: UVec2) -> Self {
+        Self::from_image(RgbaImage::new(size.x, size.y))
+    }
+
+    /// # Panics
+    /// If the image is bigger than the device supports.
+    #[must_use]
+    pub fn from_image(image: RgbaImage) -> Self {
+        Self {
+            texture: Arc::new(Texture::new(
+                Texture::from_image(&image, TextureOptions::default()).unwrap(),
+            )),
+            image,
+            dirty: DirtyRegion::default(),
+        }
+    }
+
+    /// The texture to draw, which changes once [`DynamicTexture::upload`] is called.
+    #[must_use]
+    pub fn texture(&self) -> &Arc<Texture> {
+        &self.texture
+    }
+
+    #[must_use]
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Gives mutable access to every pixel, which marks the whole texture as changed.
+    pub fn image_mut(&mut self) -> &mut RgbaImage {
+        self.dirty
+            .add(UVec2::ZERO, uvec2(self.image.width(), self.image.height()));
+        &mut self.image
+    }
+
+    pub fn set_pixel(&mut self, pos: UVec2, color: image::Rgba<u8>) {
+        self.image.put_pixel(pos.x, pos.y, color);
+        self.dirty.add(pos, UVec2::ONE);
+    }
+
+    /// Copies an image into the texture at `pos`, ignoring any part that doesn't fit.
+    pub fn write(&mut self, pos: UVec2, image: &RgbaImage) {
+        image::imageops::replace(&mut self.image, image, pos.x.into(), pos.y.into());
+        self.mark_dirty(pos, uvec2(image.width(), image.height()));
+    }
+
+    /// Marks an area as changed, such as after editing [`DynamicTexture::image_mut`] when the
+    /// area is known.
+    pub fn mark_dirty(&mut self, pos: UVec2, size: UVec2) {
+        let image_size = uvec2(self.image.width(), self.image.height());
+        let pos = pos.min(image_size);
+        self.dirty.add(pos, size.min(image_size - pos));
+    }
+
+    /// Uploads the changed area of the texture.
+    pub fn upload(&mut self) {
+        if let Some((pos, size)) = self.dirty.take() {
+            self.texture.write_subimage(pos, &self.image, pos, size);
+        }
+    }
+}
+
+/// How a texture is created when it's loaded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    /// Generates smaller versions of the texture on the GPU, so it doesn't shimmer when drawn
+    /// smaller than its size. Needs a sampler with a `mipmap_filter` to be used.
+    pub mipmaps: bool,
+    pub format: TextureFormat,
+}
+
+impl TextureOptions {
+    #[must_use]
+    pub fn with_mipmaps(self, mipmaps: bool) -> Self {
+        Self { mipmaps, ..self }
+    }
+
+    #[must_use]
+    pub fn with_format(self, format: TextureFormat) -> Self {
+        Self { format, ..self }
+    }
+}
+
+/// How the pixels of a texture are stored on the GPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// 8-bit color that is converted from sRGB when sampled, which suits most images.
+    #[default]
+    Srgb,
+    /// 8-bit color that is sampled as is, for data such as normal maps and lookup tables.
+    Linear,
+    /// A single 8-bit channel from the brightness of the image, for masks and glyphs. Sprites draw
+    /// it as the coverage of their tint, but custom shaders only get the red channel.
+    R8,
+    /// 16-bit floating point color, for HDR images.
+    Rgba16Float,
+}
+
+impl TextureFormat {
+    #[must_use]
+    pub fn bytes_per_pixel(self) -> u32 {
+        match self {
+            TextureFormat::Srgb | TextureFormat::Linear => 4,
+            TextureFormat::R8 => 1,
+            TextureFormat::Rgba16Float => 8,
+        }
+    }
+}
+
+impl TextureFormat {
+    /// The pixels of an image in this format.
+    fn pixels(self, image: DynamicImage) -> Vec<u8> {
+        match self {
+            TextureFormat::Srgb | TextureFormat::Linear => image.into_rgba8().into_raw(),
+            TextureFormat::R8 => image.into_luma8().into_raw(),
+            TextureFormat::Rgba16Float => {
+                let pixels: Vec<u16> = image
+                    .into_rgba32f()
+                    .into_raw()
+                    .into_iter()
+                    .map(|value| f16::from_f32(value).to_bits())
+                    .collect();
+                bytemuck::cast_slice(&pixels).to_vec()
+            }
+        }
+    }
+}
+
+impl From<TextureFormat> for wgpu::TextureFormat {
+    fn from(format: TextureFormat) -> Self {
+        match format {
+            TextureFormat::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Linear => wgpu::TextureFormat::Rgba8Unorm,
+            TextureFormat::R8 => wgpu::TextureFormat::R8Unorm,
+            TextureFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+/// The settings of a sampler, which decides how a texture is read when drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SamplerSettings {
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    pub mipmap_filter: FilterMode,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    /// The maximum anisotropic filtering level, from 1 to 16. Only used if every filter is
+    /// [`FilterMode::Linear`].
+    pub anisotropy: u16,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self::linear()
+    }
+}
+
+impl SamplerSettings {
+    /// Smoothly blends between pixels and mipmaps, which suits most textures.
+    #[must_use]
+    pub fn linear() -> Self {
+        Self {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            anisotropy: 1,
+        }
+    }
+
+    /// Keeps pixels sharp, which suits pixel art.
+    #[must_use]
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Self::linear()
+        }
+    }
+
+    #[must_use]
+    pub fn with_filter(self, filter: FilterMode) -> Self {
+        Self {
+            mag_filter: filter,
+            min_filter: filter,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_mipmap_filter(self, mipmap_filter: FilterMode) -> Self {
+        Self {
+            mipmap_filter,
+            ..self
+        }
+    }
+
+    /// Sets how texture coordinates outside of 0 to 1 are handled in both directions.
+    #[must_use]
+    pub fn with_address_mode(self, address_mode: AddressMode) -> Self {
+        Self {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_anisotropy(self, anisotropy: u16) -> Self {
+        Self { anisotropy, ..self }
+    }
+
+    fn create(&self) -> wgpu::Sampler {
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|filter| *filter == FilterMode::Linear);
+        RendererGlobals::get()
+            .device
+            .create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: self.address_mode_u,
+                address_mode_v: self.address_mode_v,
+                mag_filter: self.mag_filter,
+                min_filter: self.min_filter,
+                mipmap_filter: self.mipmap_filter,
+                anisotropy_clamp: if linear {
+                    self.anisotropy.clamp(1, 16)
+                } else {
+                    1
+                },
+                ..Default::default()
+            })
+    }
+}
+
+/// Renders each mip level of a texture from the level above it.
+#[derive(Debug)]
+pub(crate) struct MipmapGenerator {
+    sampler: wgpu::Sampler,
+    pipelines: FxHashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+}
+
+impl MipmapGenerator {
+    pub(crate) fn new() -> Self {
+        Self {
+            sampler: SamplerSettings::linear().create(),
+            pipelines: FxHashMap::default(),
+        }
+    }
+
+    pub(crate) fn generate(&mut self, texture: &wgpu::Texture) {
+        let device = &RendererGlobals::get().device;
+        let pipeline = self.pipelines.entry(texture.format()).or_insert_with(|| {
+            let shader = device.create_shader_module(include_wgsl!("mipmap.wgsl"));
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("mipmap pipeline"),
+                layout: None,
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(texture.format().into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("mipmap encoder"),
+        });
+        let views: Vec<_> = (0..texture.mip_level_count())
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        for pair in views.windows(2) {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&pair[0]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("mipmap pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &pair[1],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        RendererGlobals::get()
+            .queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+/// Where a texture is loaded from, along with the key it's cached under. The key is found once
+/// when the source is made, so loading the same source again doesn't hash its contents again.
+#[derive(Clone, Debug)]
+pub struct TextureSource {
+    data: SourceData,
+    key: TextureKey,
+}
+
+#[derive(Clone, Debug)]
+enum SourceData {
+    Path(PathBuf),
     Image(RgbaImage),
-    Memory(&'a [u8]),
+    Memory(Cow<'static, [u8]>),
+}
+
+impl TextureSource {
+    /// An image file, keyed by its path.
+    #[must_use]
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            key: TextureKey::Path(path.clone()),
+            data: SourceData::Path(path),
+        }
+    }
+
+    /// Pixels, keyed by their size and a hash of them.
+    #[must_use]
+    pub fn image(image: RgbaImage) -> Self {
+        Self {
+            key: TextureKey::Image {
+                size: uvec2(image.width(), image.height()),
+                hash: content_hash(image.as_raw()),
+            },
+            data: SourceData::Image(image),
+        }
+    }
+
+    /// An encoded image file in memory. Static memory, such as from `include_bytes!`, is keyed by
+    /// its address, and owned memory by its length and a hash of it.
+    #[must_use]
+    pub fn memory(bytes: impl Into<Cow<'static, [u8]>>) -> Self {
+        let bytes = bytes.into();
+        Self {
+            key: match &bytes {
+                Cow::Borrowed(bytes) => TextureKey::Static(bytes.as_ptr() as usize, bytes.len()),
+                Cow::Owned(bytes) => TextureKey::Bytes {
+                    len: bytes.len(),
+                    hash: content_hash(bytes),
+                },
+            },
+            data: SourceData::Memory(bytes),
+        }
+    }
+
+    /// The key the texture is cached under.
+    #[must_use]
+    pub fn key(&self) -> &TextureKey {
+        &self.key
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct TextureManager<'a> {
-    textures: FxHashMap<TextureSource<'a>, Arc<Texture>>,
-    linear_sampler: Option<wgpu::Sampler>,
-    nearest_sampler: Option<wgpu::Sampler>,
+/// A 128 bit hash of some contents, from two independently keyed SipHashes, so different
+/// contents practically never share a key. Only stable for the life of the process.
+fn content_hash(bytes: &[u8]) -> u128 {
+    static STATES: OnceLock<[RandomState; 2]> = OnceLock::new();
+    let [low, high] = STATES.get_or_init(|| [RandomState::new(), RandomState::new()]);
+    let hash = |state: &RandomState| {
+        let mut hasher = state.build_hasher();
+        hasher.write(bytes);
+        hasher.finish()
+    };
+    u128::from(hash(high)) << 64 | u128::from(hash(low))
 }
 
-impl<'a> TextureManager<'a> {
-    pub fn load(&mut self, source: &TextureSource<'a>) -> Arc<Texture> {
-        self.textures
-            .entry(source.clone()) // TODO cloning can't be good here
-            .or_insert_with(|| {
-                let image = match source {
-                    TextureSource::Path(path) => {
-                        Cow::Owned(image::open(path).unwrap().into_rgba8())
+impl TextureSource {
+    /// The file the texture is loaded from, if any.
+    fn file(&self) -> Option<PathBuf> {
+        match &self.data {
+            SourceData::Path(path) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    /// Decodes the image and converts it to the pixels of a format.
+    fn decode(self, format: TextureFormat) -> Result<(UVec2, Vec<u8>), TextureError> {
+        let image = match self.data {
+            SourceData::Path(path) => image::open(path)?,
+            SourceData::Image(image) => DynamicImage::ImageRgba8(image),
+            SourceData::Memory(bytes) => image::load_from_memory(&bytes)?,
+        };
+        Ok((uvec2(image.width(), image.height()), format.pixels(image)))
+    }
+}
+
+/// What a texture is cached under in a [`TextureManager`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum TextureKey {
+    Path(PathBuf),
+    /// A name or ID chosen by the user.
+    Name(Cow<'static, str>),
+    /// The size and a hash of the pixels of a [`TextureSource::image`].
+    Image {
+        size: UVec2,
+        hash: u128,
+    },
+    /// The length and a hash of the owned bytes of a [`TextureSource::memory`].
+    Bytes {
+        len: usize,
+        hash: u128,
+    },
+    /// The address and length of static data, such as from `include_bytes!`.
+    Static(usize, usize),
+}
+
+impl From<&'static str> for TextureKey {
+    fn from(name: &'static str) -> Self {
+        TextureKey::Name(Cow::Borrowed(name))
+    }
+}
+
+impl From<String> for TextureKey {
+    fn from(name: String) -> Self {
+        TextureKey::Name(Cow::Owned(name))
+    }
+}
+
+/// Why a texture couldn't be loaded.
+#[derive(Debug)]
+pub enum TextureError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The file isn't a supported image or is corrupt.
+    Decode(image::ImageError),
+    /// The image is bigger than the device supports.
+    TooLarge { size: UVec2, max: u32 },
+    /// The texture can't be created or written to in the format.
+    UnsupportedFormat(TextureFormat),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::Io(e) => write!(f, "failed to read texture: {e}"),
+            TextureError::Decode(e) => write!(f, "failed to decode texture: {e}"),
+            TextureError::TooLarge { size, max } => write!(
+                f,
+                "texture is {}x{} but the device supports at most {max}x{max}",
+                size.x, size.y
+            ),
+            TextureError::UnsupportedFormat(format) => {
+                write!(f, "textures in the {format:?} format aren't supported here")
+            }
+        }
+    }
+}
+
+impl Error for TextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TextureError::Io(e) => Some(e),
+            TextureError::Decode(e) => Some(e),
+            TextureError::TooLarge { .. } | TextureError::UnsupportedFormat(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TextureError {
+    fn from(e: std::io::Error) -> Self {
+        TextureError::Io(e)
+    }
+}
+
+impl From<image::ImageError> for TextureError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => TextureError::Io(e),
+            e => TextureError::Decode(e),
+        }
+    }
+}
+
+/// A texture that is loading in the background, from [`TextureManager::load_async`]. Cloning it
+/// is cheap and every clone resolves at the same time.
+#[derive(Clone, Debug)]
+pub struct TextureHandle(Arc<HandleState>);
+
+#[derive(Debug)]
+struct HandleState {
+    placeholder: Arc<Texture>,
+    result: OnceLock<Result<Arc<Texture>, Arc<TextureError>>>,
+    /// Shown instead of the placeholder when loading failed with fallback on.
+    missing: OnceLock<Arc<Texture>>,
+}
+
+impl TextureHandle {
+    fn new(placeholder: Arc<Texture>) -> Self {
+        Self(Arc::new(HandleState {
+            placeholder,
+            result: OnceLock::new(),
+            missing: OnceLock::new(),
+        }))
+    }
+
+    /// A handle to a texture that is already loaded.
+    pub(crate) fn loaded(texture: Arc<Texture>) -> Self {
+        let handle = Self::new(texture.clone());
+        handle.resolve(Ok(texture));
+        handle
+    }
+
+    fn resolve(&self, result: Result<Arc<Texture>, TextureError>) {
+        let _ = self.0.result.set(result.map_err(Arc::new));
+    }
+
+    /// Fails the load, showing `missing` in place of the texture.
+    fn fail_with_fallback(&self, error: TextureError, missing: Arc<Texture>) {
+        let _ = self.0.missing.set(missing);
+        self.resolve(Err(error));
+    }
+
+    /// Whether the texture has finished loading, successfully or not.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.0.result.get().is_some()
+    }
+
+    /// The texture, if it has loaded successfully.
+    #[must_use]
+    pub fn get(&self) -> Option<&Arc<Texture>> {
+        self.0.result.get()?.as_ref().ok()
+    }
+
+    /// Why the texture failed to load, if it did.
+    #[must_use]
+    pub fn error(&self) -> Option<&TextureError> {
+        self.0.result.get()?.as_ref().err().map(AsRef::as_ref)
+    }
+
+    /// The texture if it has loaded, the missing texture if it failed with
+    /// [`TextureManager::fallback`] set, otherwise a transparent placeholder.
+    #[must_use]
+    pub fn texture(&self) -> &Arc<Texture> {
+        self.get()
+            .or(self.0.missing.get())
+            .unwrap_or(&self.0.placeholder)
+    }
+}
+
+/// How many of the textures queued with [`TextureManager::load_async`] have loaded, counting
+/// from when the queue was last empty.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LoadProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    /// The amount loaded from 0 to 1, which is 1 when nothing is loading.
+    #[must_use]
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.total as f32
+        }
+    }
+
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.completed >= self.total
+    }
+}
+
+type LoadKey = (TextureKey, TextureOptions);
+type LoadResult = (LoadKey, Result<(UVec2, Vec<u8>), TextureError>);
+
+/// Threads that decode images, which are uploaded when the results are polled.
+#[derive(Debug)]
+struct Loader {
+    jobs: mpsc::Sender<(LoadKey, TextureSource)>,
+    results: mpsc::Receiver<LoadResult>,
+}
+
+impl Loader {
+    fn new() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<(LoadKey, TextureSource)>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let threads = thread::available_parallelism().map_or(1, |n| n.get().clamp(1, 4));
+        for _ in 0..threads {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            thread::Builder::new()
+                .name("texture loader".to_owned())
+                .spawn(move || loop {
+                    // The lock is released before decoding so other threads can take jobs
+                    let job = job_receiver.lock().unwrap().recv();
+                    let Ok((key, source)) = job else {
+                        break;
+                    };
+                    let result = source.decode(key.1.format);
+                    if result_sender.send((key, result)).is_err() {
+                        break;
                     }
-                    TextureSource::Image(image) => Cow::Borrowed(image),
-                    TextureSource::Memory(bytes) => {
-                        Cow::Owned(image::load_from_memory(bytes).unwrap().into_rgba8())
+                })
+                .unwrap();
+        }
+        Self { jobs, results }
+    }
+}
+
+/// The memory used by the textures in a [`TextureManager`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextureStats {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+#[derive(Debug)]
+struct CachedTexture {
+    texture: Arc<Texture>,
+    last_used: u64,
+    /// The file the texture was loaded from, to reload it when it changes.
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct TextureManager {
+    textures: FxHashMap<(TextureKey, TextureOptions), CachedTexture>,
+    /// Increased every time a texture is loaded, to find the least recently used textures.
+    tick: u64,
+    bytes: usize,
+    samplers: FxHashMap<SamplerSettings, Arc<wgpu::Sampler>>,
+    mipmaps: Option<MipmapGenerator>,
+    missing: Option<Arc<Texture>>,
+    placeholder: Option<Arc<Texture>>,
+    loader: Option<Loader>,
+    loading: FxHashMap<LoadKey, (TextureHandle, Option<PathBuf>)>,
+    progress: LoadProgress,
+    watcher: Option<FileWatcher>,
+    /// Loads that fell back to the missing texture, and the files they came from, so they aren't
+    /// retried and logged every time.
+    failed: FxHashMap<LoadKey, Option<PathBuf>>,
+    /// Why changed files couldn't be reloaded, until they reload successfully.
+    reload_errors: FxHashMap<PathBuf, TextureError>,
+    /// The options used by [`TextureManager::load`] and [`TextureManager::load_with_key`].
+    pub options: TextureOptions,
+    /// If set, [`TextureManager::load`] returns a checkerboard instead of panicking when a texture
+    /// fails to load. Defaults to on in release builds.
+    pub fallback: bool,
+    /// If set, the least recently used textures are freed when the cache uses more GPU memory
+    /// than this many bytes. Textures that are still in use are never freed.
+    pub budget: Option<usize>,
+}
+
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self {
+            textures: FxHashMap::default(),
+            tick: 0,
+            bytes: 0,
+            samplers: FxHashMap::default(),
+            mipmaps: None,
+            missing: None,
+            placeholder: None,
+            loader: None,
+            loading: FxHashMap::default(),
+            progress: LoadProgress::default(),
+            watcher: None,
+            failed: FxHashMap::default(),
+            reload_errors: FxHashMap::default(),
+            options: TextureOptions::default(),
+            fallback: !cfg!(debug_assertions),
+            budget: None,
+        }
+    }
+}
+
+impl TextureManager {
+    /// Loads a texture cached under the key of the source, or returns the already loaded one.
+    ///
+    /// # Panics
+    /// If the texture fails to load and [`TextureManager::fallback`] isn't set.
+    pub fn load(&mut self, source: &TextureSource) -> Arc<Texture> {
+        self.load_with_key(source.key().clone(), source)
+    }
+
+    /// Loads a texture cached under `key`, or returns the already loaded one without looking at
+    /// `source`.
+    ///
+    /// # Panics
+    /// If the texture fails to load and [`TextureManager::fallback`] isn't set.
+    pub fn load_with_key(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: &TextureSource,
+    ) -> Arc<Texture> {
+        self.load_with_options(key, source, self.options)
+    }
+
+    /// Loads a texture cached under `key` with its own options, or returns the already loaded
+    /// one without looking at `source`. With [`TextureManager::fallback`] set, a failure is logged
+    /// once and the key keeps returning the missing texture until [`TextureManager::clear`] is
+    /// called or, with hot reloading on, the file changes.
+    ///
+    /// # Panics
+    /// If the texture fails to load and [`TextureManager::fallback`] isn't set.
+    pub fn load_with_options(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: &TextureSource,
+        options: TextureOptions,
+    ) -> Arc<Texture> {
+        let key = (key.into(), options);
+        if self.failed.contains_key(&key) {
+            return self.missing_texture();
+        }
+        match self.try_load_with_options(key.0.clone(), source, options) {
+            Ok(texture) => texture,
+            Err(e) if self.fallback => {
+                eprintln!("failed to load {:?}: {e}", key.0);
+                let path = source.file();
+                if let (Some(watcher), Some(path)) = (&mut self.watcher, &path) {
+                    watcher.watch(path);
+                }
+                self.failed.insert(key, path);
+                self.missing_texture()
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Loads a texture cached under the key of the source, or returns the already loaded one.
+    pub fn try_load(&mut self, source: &TextureSource) -> Result<Arc<Texture>, TextureError> {
+        self.try_load_with_key(source.key().clone(), source)
+    }
+
+    /// Loads a texture cached under `key`, or returns the already loaded one without looking at
+    /// `source`.
+    pub fn try_load_with_key(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: &TextureSource,
+    ) -> Result<Arc<Texture>, TextureError> {
+        self.try_load_with_options(key, source, self.options)
+    }
+
+    /// Loads a texture cached under `key` with its own options, or returns the already loaded
+    /// one without looking at `source`.
+    pub fn try_load_with_options(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: &TextureSource,
+        options: TextureOptions,
+    ) -> Result<Arc<Texture>, TextureError> {
+        let key = (key.into(), options);
+        self.tick += 1;
+        if let Some(cached) = self.textures.get_mut(&key) {
+            cached.last_used = self.tick;
+            return Ok(cached.texture.clone());
+        }
+
+        let texture = match &source.data {
+            SourceData::Path(path) => Texture::from_dynamic(image::open(path)?, options)?,
+            SourceData::Image(image) => match options.format {
+                TextureFormat::Srgb | TextureFormat::Linear => Texture::from_image(image, options)?,
+                _ => Texture::from_dynamic(DynamicImage::ImageRgba8(image.clone()), options)?,
+            },
+            SourceData::Memory(bytes) => {
+                Texture::from_dynamic(image::load_from_memory(bytes)?, options)?
+            }
+        };
+        Ok(self.insert(key, texture, source.file()))
+    }
+
+    /// The texture cached under `key` with the options, if it has been loaded.
+    pub fn get(
+        &mut self,
+        key: impl Into<TextureKey>,
+        options: TextureOptions,
+    ) -> Option<Arc<Texture>> {
+        let cached = self.textures.get_mut(&(key.into(), options))?;
+        self.tick += 1;
+        cached.last_used = self.tick;
+        Some(cached.texture.clone())
+    }
+
+    /// Generates the mipmaps of a new texture and caches it.
+    fn insert(
+        &mut self,
+        key: LoadKey,
+        texture: wgpu::Texture,
+        path: Option<PathBuf>,
+    ) -> Arc<Texture> {
+        if key.1.mipmaps {
+            self.mipmaps
+                .get_or_insert_with(MipmapGenerator::new)
+                .generate(&texture);
+        }
+        let texture = Arc::new(Texture::new(texture));
+        if let (Some(watcher), Some(path)) = (&mut self.watcher, &path) {
+            watcher.watch(path);
+        }
+        self.textures.insert(
+            key,
+            CachedTexture {
+                texture: texture.clone(),
+                last_used: self.tick,
+                path,
+            },
+        );
+        self.bytes += texture.bytes();
+        if let Some(budget) = self.budget {
+            self.evict(budget);
+        }
+        texture
+    }
+
+    /// Starts decoding a texture on a background thread. It's uploaded during a later
+    /// [`TextureManager::poll`], which is called before every update.
+    pub fn load_async(&mut self, source: TextureSource) -> TextureHandle {
+        self.load_async_with_options(source.key().clone(), source, self.options)
+    }
+
+    /// Starts decoding a texture on a background thread, cached under `key`.
+    pub fn load_async_with_key(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: TextureSource,
+    ) -> TextureHandle {
+        self.load_async_with_options(key, source, self.options)
+    }
+
+    /// Starts decoding a texture on a background thread, cached under `key` with its own options.
+    /// With [`TextureManager::fallback`] set, a failure is remembered like with
+    /// [`TextureManager::load_with_options`], and the key resolves to the missing texture straight
+    /// away until it's retried.
+    pub fn load_async_with_options(
+        &mut self,
+        key: impl Into<TextureKey>,
+        source: TextureSource,
+        options: TextureOptions,
+    ) -> TextureHandle {
+        let key = (key.into(), options);
+        self.tick += 1;
+        if let Some(cached) = self.textures.get_mut(&key) {
+            cached.last_used = self.tick;
+            return TextureHandle::loaded(cached.texture.clone());
+        }
+        if self.failed.contains_key(&key) {
+            return TextureHandle::loaded(self.missing_texture());
+        }
+        if let Some((handle, _)) = self.loading.get(&key) {
+            return handle.clone();
+        }
+
+        if self.loading.is_empty() {
+            self.progress = LoadProgress::default();
+        }
+        self.progress.total += 1;
+        let handle = TextureHandle::new(self.placeholder_texture());
+        self.loading
+            .insert(key.clone(), (handle.clone(), source.file()));
+        self.loader
+            .get_or_insert_with(Loader::new)
+            .jobs
+            .send((key, source))
+            .unwrap();
+        handle
+    }
+
+    /// Uploads the textures that have finished decoding in the background, and reloads changed
+    /// files if hot reloading is on.
+    pub fn poll(&mut self) {
+        self.hot_reload();
+        let Some(loader) = &self.loader else {
+            return;
+        };
+        let results: Vec<_> = loader.results.try_iter().collect();
+        for (key, result) in results {
+            let Some((handle, path)) = self.loading.remove(&key) else {
+                continue;
+            };
+            let options = key.1;
+            let result = result.and_then(|(size, pixels)| Texture::create(size, &pixels, options));
+            match result {
+                Ok(texture) => handle.resolve(Ok(self.insert(key, texture, path))),
+                Err(e) if self.fallback => {
+                    if let (Some(watcher), Some(path)) = (&mut self.watcher, &path) {
+                        watcher.watch(path);
+                    }
+                    self.failed.insert(key, path);
+                    handle.fail_with_fallback(e, self.missing_texture());
+                }
+                Err(e) => handle.resolve(Err(e)),
+            }
+            self.progress.completed += 1;
+        }
+    }
+
+    #[must_use]
+    pub fn progress(&self) -> LoadProgress {
+        self.progress
+    }
+
+    /// Reloads textures loaded from [`TextureSource::path`] when their files change, checking at
+    /// most once per `interval`, or stops if `None`. Sprites using them switch to the new
+    /// textures without changing their handles.
+    pub fn set_hot_reload(&mut self, interval: Option<Duration>) {
+        self.watcher = interval.map(|interval| {
+            let mut watcher = FileWatcher::new(interval);
+            for path in self
+                .textures
+                .values()
+                .filter_map(|cached| cached.path.as_ref())
+                .chain(self.failed.values().flatten())
+            {
+                watcher.watch(path);
+            }
+            watcher
+        });
+    }
+
+    fn hot_reload(&mut self) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        for path in watcher.changed() {
+            // Let failed loads try again
+            let failed = self.failed.len();
+            self.failed
+                .retain(|_, failed| failed.as_ref() != Some(&path));
+            let mut found = self.failed.len() != failed;
+            for ((_, options), cached) in &mut self.textures {
+                if cached.path.as_ref() != Some(&path) {
+                    continue;
+                }
+                found = true;
+                let texture = TextureSource::path(path.clone())
+                    .decode(options.format)
+                    .and_then(|(size, pixels)| Texture::create(size, &pixels, *options));
+                match texture {
+                    Ok(texture) => {
+                        if options.mipmaps {
+                            self.mipmaps
+                                .get_or_insert_with(MipmapGenerator::new)
+                                .generate(&texture);
+                        }
+                        self.bytes -= cached.texture.bytes();
+                        cached.texture.replace(texture);
+                        self.bytes += cached.texture.bytes();
+                        self.reload_errors.remove(&path);
+                    }
+                    Err(e) => {
+                        self.reload_errors.insert(path.clone(), e);
                     }
-                };
+                }
+            }
+            if !found {
+                watcher.unwatch(&path);
+            }
+        }
+    }
+
+    /// The files that changed but couldn't be reloaded and why, so the old textures are still
+    /// being drawn. A file is removed once it reloads successfully.
+    pub fn reload_errors(&self) -> impl Iterator<Item = (&Path, &TextureError)> {
+        self.reload_errors
+            .iter()
+            .map(|(path, error)| (path.as_path(), error))
+    }
+
+    /// A transparent pixel that stands in for textures that are loading.
+    pub fn placeholder_texture(&mut self) -> Arc<Texture> {
+        self.placeholder
+            .get_or_insert_with(|| {
+                Arc::new(Texture::new(
+                    Texture::from_image(&RgbaImage::new(1, 1), TextureOptions::default()).unwrap(),
+                ))
+            })
+            .clone()
+    }
 
+    /// A magenta and black checkerboard that stands in for textures that failed to load.
+    pub fn missing_texture(&mut self) -> Arc<Texture> {
+        self.missing
+            .get_or_insert_with(|| {
+                let image = RgbaImage::from_fn(8, 8, |x, y| {
+                    if (x / 4 + y / 4) % 2 == 0 {
+                        image::Rgba([255, 0, 255, 255])
+                    } else {
+                        image::Rgba([0, 0, 0, 255])
+                    }
+                });
                 Arc::new(Texture::new(
-                    RendererGlobals::get().device.create_texture_with_data(
-                        &RendererGlobals::get().queue,
-                        &wgpu::TextureDescriptor {
-                            label: None,
-                            size: wgpu::Extent3d {
-                                width: image.width(),
-                                height: image.height(),
-                                depth_or_array_layers: 1,
-                            },
-                            mip_level_count: 1,
-                            sample_count: 1,
-                            dimension: wgpu::TextureDimension::D2,
-                            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                                | wgpu::TextureUsages::COPY_DST,
-                            view_formats: &[],
-                        },
-                        &image,
-                    ),
+                    Texture::from_image(&image, TextureOptions::default()).unwrap(),
                 ))
             })
             .clone()
     }
 
+    /// Frees every texture that is only referenced by the cache, and forgets failed loads so they
+    /// are tried again.
     pub fn clear(&mut self) {
+        self.failed.clear();
+        self.reload_errors.clear();
         self.textures
-            .retain(|_, texture| Arc::strong_count(texture) != 0);
+            .retain(|_, cached| Arc::strong_count(&cached.texture) > 1);
+        self.bytes = self
+            .textures
+            .values()
+            .map(|cached| cached.texture.bytes())
+            .sum();
+    }
+
+    /// Forgets the textures cached under `key` with any options and any failure to load them, so
+    /// they're loaded again next time. Textures that are still in use aren't freed until they're
+    /// dropped.
+    pub fn remove(&mut self, key: &TextureKey) {
+        self.failed.retain(|(failed, _), _| failed != key);
+        let bytes = &mut self.bytes;
+        self.textures.retain(|(cached, _), texture| {
+            let keep = cached != key;
+            if !keep {
+                *bytes -= texture.texture.bytes();
+            }
+            keep
+        });
+    }
+
+    /// Frees the least recently used textures that are only referenced by the cache until the
+    /// cache uses at most `budget` bytes, or nothing else can be freed.
+    pub fn evict(&mut self, budget: usize) {
+        if self.bytes <= budget {
+            return;
+        }
+        let mut unused: Vec<_> = self
+            .textures
+            .iter()
+            .filter(|(_, cached)| Arc::strong_count(&cached.texture) == 1)
+            .map(|(key, cached)| (cached.last_used, key.clone()))
+            .collect();
+        unused.sort_unstable_by_key(|(last_used, _)| *last_used);
+        for (_, key) in unused {
+            if self.bytes <= budget {
+                break;
+            }
+            if let Some(cached) = self.textures.remove(&key) {
+                self.bytes -= cached.texture.bytes();
+            }
+        }
     }
 
     #[must_use]
-    pub fn linear_sampler(&mut self) -> &wgpu::Sampler {
-        self.linear_sampler.get_or_insert_with(|| {
-            RendererGlobals::get()
-                .device
-                .create_sampler(&wgpu::SamplerDescriptor {
-                    mag_filter: wgpu::FilterMode::Linear,
-                    ..Default::default()
-                })
-        })
+    pub fn stats(&self) -> TextureStats {
+        TextureStats {
+            count: self.textures.len(),
+            bytes: self.bytes,
+        }
     }
 
+    /// Returns a sampler with these settings, creating it the first time.
     #[must_use]
-    pub fn nearest_sampler(&mut self) -> &wgpu::Sampler {
-        self.nearest_sampler.get_or_insert_with(|| {
-            RendererGlobals::get()
-                .device
-                .create_sampler(&wgpu::SamplerDescriptor {
-                    mag_filter: wgpu::FilterMode::Nearest,
-                    ..Default::default()
-                })
-        })
+    pub fn sampler(&mut self, settings: SamplerSettings) -> &Arc<wgpu::Sampler> {
+        self.samplers
+            .entry(settings)
+            .or_insert_with(|| Arc::new(settings.create()))
+    }
+
+    #[must_use]
+    pub fn linear_sampler(&mut self) -> &Arc<wgpu::Sampler> {
+        self.sampler(SamplerSettings::linear())
+    }
+
+    #[must_use]
+    pub fn nearest_sampler(&mut self) -> &Arc<wgpu::Sampler> {
+        self.sampler(SamplerSettings::nearest())
     }
 }
+
+#[test]
+fn texture_error_test() {
+    let mut manager = TextureManager::default();
+    assert!(matches!(
+        manager.try_load(&TextureSource::path("does/not/exist.png")),
+        Err(TextureError::Io(_))
+    ));
+    assert!(matches!(
+        manager.try_load(&TextureSource::memory(b"not an image".as_slice())),
+        Err(TextureError::Decode(_))
+    ));
+}
+
+#[test]
+fn texture_key_test() {
+    static BYTES: &[u8] = b"bytes";
+    let borrowed = TextureSource::memory(Cow::Borrowed(BYTES));
+    assert_eq!(borrowed.key(), borrowed.clone().key());
+    let owned = TextureSource::memory(Cow::Owned(BYTES.to_vec()));
+    assert_ne!(borrowed.key(), owned.key());
+    assert_eq!(owned.key(), owned.clone().key());
+
+    let image = RgbaImage::new(2, 2);
+    assert_eq!(
+        TextureSource::image(image.clone()).key(),
+        TextureSource::image(image).key()
+    );
+    assert_ne!(
+        TextureSource::image(RgbaImage::new(1, 4)).key(),
+        TextureSource::image(RgbaImage::new(2, 2)).key()
+    );
+
+    // Pixels and encoded bytes with the same contents are different textures
+    let image = RgbaImage::from_raw(1, 2, b"12345678".to_vec()).unwrap();
+    let bytes = TextureSource::memory(Cow::Owned(image.as_raw().clone()));
+    assert_ne!(TextureSource::image(image).key(), bytes.key());
+    assert_ne!(content_hash(b"a"), content_hash(b"b"));
+}
+
+#[test]
+fn dirty_region_test() {
+    let mut dirty = DirtyRegion::default();
+    assert_eq!(dirty.take(), None);
+    dirty.add(uvec2(4, 2), uvec2(2, 2));
+    dirty.add(uvec2(1, 5), UVec2::ONE);
+    assert_eq!(dirty.take(), Some((uvec2(1, 2), uvec2(5, 4))));
+    assert_eq!(dirty.take(), None);
+}
+
+#[test]
+fn decode_test() {
+    let source = TextureSource::memory(include_bytes!("../examples/test.png").as_slice());
+    let (size, pixels) = source.clone().decode(TextureFormat::Srgb).unwrap();
+    assert_eq!(pixels.len(), (size.x * size.y * 4) as usize);
+    let (size, pixels) = source.decode(TextureFormat::R8).unwrap();
+    assert_eq!(pixels.len(), (size.x * size.y) as usize);
+    let white = TextureSource::image(RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])));
+    let (_, pixels) = white.decode(TextureFormat::Rgba16Float).unwrap();
+    assert_eq!(pixels, f16::ONE.to_bits().to_ne_bytes().repeat(4));
+
+    let progress = LoadProgress {
+        completed: 1,
+        total: 4,
+    };
+    assert_eq!(progress.fraction(), 0.25);
+    assert!(!progress.is_done());
+    assert_eq!(LoadProgress::default().fraction(), 1.0);
+}
@@ -1,6 +1,6 @@
-use std::mem::size_of;
+use std::ops::Mul;
 
-use glam::{Mat4, Quat, Vec3};
+use glam::{Affine2, EulerRot, Mat4, Quat, Vec2, Vec3};
 
 use crate::texture::Texture;
 
@@ -39,7 +39,7 @@ impl Transform {
     #[must_use]
     pub fn texture_scale(texture: &Texture) -> Self {
         Self {
-            scale: texture.size.as_vec2().extend(1.0),
+            scale: texture.size().as_vec2().extend(1.0),
             ..Default::default()
         }
     }
@@ -65,7 +65,7 @@ impl Transform {
     #[must_use]
     pub fn with_texture_scale(self, texture: &Texture) -> Self {
         Self {
-            scale: texture.size.as_vec2().extend(1.0),
+            scale: texture.size().as_vec2().extend(1.0),
             ..self
         }
     }
@@ -75,35 +75,6 @@ impl Transform {
         f(&mut self);
         self
     }
-
-    pub(crate) fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: size_of::<Mat4>() as u64,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: 0,
-                    shader_location: 0,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: size_of::<[f32; 4]>() as u64,
-                    shader_location: 1,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: size_of::<[f32; 8]>() as u64,
-                    shader_location: 2,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float32x4,
-                    offset: size_of::<[f32; 12]>() as u64,
-                    shader_location: 3,
-                },
-            ],
-        }
-    }
 }
 
 impl From<&Transform> for Mat4 {
@@ -121,3 +92,220 @@ impl Default for Transform {
         }
     }
 }
+
+/// A 2D transform that converts to a [`Transform`] rotating around the Z axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    pub position: Vec2,
+    /// The counter-clockwise rotation in radians.
+    pub angle: f32,
+    pub scale: Vec2,
+    /// The depth, where higher values are drawn on top.
+    pub z: f32,
+}
+
+impl Transform2D {
+    #[must_use]
+    pub fn position(position: Vec2) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn angle(angle: f32) -> Self {
+        Self {
+            angle,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn scale(scale: Vec2) -> Self {
+        Self {
+            scale,
+            ..Default::default()
+        }
+    }
+
+    /// Scales to the size of a texture, where `pixels_per_unit` pixels make up one world unit.
+    #[must_use]
+    pub fn texture_scale(texture: &Texture, pixels_per_unit: f32) -> Self {
+        Self {
+            scale: texture.size().as_vec2() / pixels_per_unit,
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_position(self, position: Vec2) -> Self {
+        Self { position, ..self }
+    }
+
+    #[must_use]
+    pub fn with_angle(self, angle: f32) -> Self {
+        Self { angle, ..self }
+    }
+
+    #[must_use]
+    pub fn with_scale(self, scale: Vec2) -> Self {
+        Self { scale, ..self }
+    }
+
+    #[must_use]
+    pub fn with_z(self, z: f32) -> Self {
+        Self { z, ..self }
+    }
+
+    /// The direction the transform is facing, which is positive X when unrotated.
+    #[must_use]
+    pub fn forward(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+
+    /// Rotates to face a point.
+    pub fn look_at(&mut self, target: Vec2) {
+        let direction = target - self.position;
+        if direction != Vec2::ZERO {
+            self.angle = direction.y.atan2(direction.x);
+        }
+    }
+
+    /// Rotates counter-clockwise around a point, turning to match.
+    pub fn rotate_around(&mut self, point: Vec2, angle: f32) {
+        self.position = point + Vec2::from_angle(angle).rotate(self.position - point);
+        self.angle += angle;
+    }
+
+    /// Converts a point from the transform's local space to its parent's space.
+    #[must_use]
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        Affine2::from(*self).transform_point2(point)
+    }
+
+    /// The transform that undoes this one. Non-uniform scales combined with rotation can't be
+    /// represented exactly, so are approximated.
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        Self::from_affine(Affine2::from(*self).inverse(), -self.z)
+    }
+
+    /// Applies `other` inside the space of this transform, like a child of this transform. Has the
+    /// same limitations as [`Transform2D::inverse`].
+    #[must_use]
+    pub fn compose(&self, other: &Self) -> Self {
+        Self::from_affine(
+            Affine2::from(*self) * Affine2::from(*other),
+            self.z + other.z,
+        )
+    }
+
+    fn from_affine(affine: Affine2, z: f32) -> Self {
+        let (scale, angle, position) = affine.to_scale_angle_translation();
+        Self {
+            position,
+            angle,
+            scale,
+            z,
+        }
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            angle: 0.0,
+            scale: Vec2::ONE,
+            z: 0.0,
+        }
+    }
+}
+
+impl Mul for Transform2D {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.compose(&rhs)
+    }
+}
+
+impl From<Transform2D> for Affine2 {
+    fn from(value: Transform2D) -> Self {
+        Self::from_scale_angle_translation(value.scale, value.angle, value.position)
+    }
+}
+
+impl From<Transform2D> for Transform {
+    fn from(value: Transform2D) -> Self {
+        Self {
+            translation: value.position.extend(value.z),
+            rotation: Quat::from_rotation_z(value.angle),
+            scale: value.scale.extend(1.0),
+        }
+    }
+}
+
+impl From<Transform> for Transform2D {
+    /// Drops any rotation that isn't around the Z axis.
+    fn from(value: Transform) -> Self {
+        Self {
+            position: value.translation.truncate(),
+            angle: value.rotation.to_euler(EulerRot::ZYX).0,
+            scale: value.scale.truncate(),
+            z: value.translation.z,
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_transform_eq(a: Transform2D, b: Transform2D) {
+    assert!(
+        a.position.abs_diff_eq(b.position, 1e-4)
+            && (a.angle - b.angle).abs() < 1e-4
+            && a.scale.abs_diff_eq(b.scale, 1e-4)
+            && (a.z - b.z).abs() < 1e-4,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn transform_2d_conversion_test() {
+    let transform = Transform2D::position(Vec2::new(1.0, 2.0))
+        .with_angle(0.5)
+        .with_scale(Vec2::new(2.0, 3.0))
+        .with_z(-1.0);
+    assert_transform_eq(Transform2D::from(Transform::from(transform)), transform);
+    assert!(Mat4::from(&Transform::from(transform))
+        .transform_point3(Vec3::X)
+        .truncate()
+        .abs_diff_eq(transform.transform_point(Vec2::X), 1e-5));
+}
+
+#[test]
+fn transform_2d_look_test() {
+    let mut transform = Transform2D::position(Vec2::ONE);
+    transform.look_at(Vec2::new(1.0, 5.0));
+    assert!(transform.forward().abs_diff_eq(Vec2::Y, 1e-5));
+
+    transform.rotate_around(Vec2::ZERO, std::f32::consts::PI);
+    assert!(transform.position.abs_diff_eq(Vec2::NEG_ONE, 1e-5));
+    assert!(transform.forward().abs_diff_eq(Vec2::NEG_Y, 1e-5));
+}
+
+#[test]
+fn transform_2d_compose_test() {
+    let parent = Transform2D::position(Vec2::new(3.0, 0.0))
+        .with_angle(std::f32::consts::FRAC_PI_2)
+        .with_scale(Vec2::splat(2.0));
+    let child = Transform2D::position(Vec2::X).with_angle(0.25);
+    let world = parent * child;
+    assert_transform_eq(
+        world,
+        Transform2D::position(Vec2::new(3.0, 2.0))
+            .with_angle(std::f32::consts::FRAC_PI_2 + 0.25)
+            .with_scale(Vec2::splat(2.0)),
+    );
+    assert_transform_eq(parent.inverse() * world, child);
+}
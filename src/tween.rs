@@ -0,0 +1,824 @@
+//! Animating values over time with easing.
+
+use std::{f32::consts::PI, time::Duration};
+
+use glam::{Quat, Vec2, Vec3};
+
+use crate::{
+    handle::{impl_handle, HandleSlab},
+    sprite::{Color, SpriteHandle},
+    transform::Transform,
+    viewport::ViewportHandle,
+    GameData,
+};
+
+/// Curves that change how a tween progresses from start to end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// Maps progress between 0 and 1 to an eased amount, which starts at 0 and ends at 1 but may
+    /// overshoot in between.
+    #[must_use]
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+        const ELASTIC: f32 = 2.0 * PI / 3.0;
+        const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC).sin()
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0
+                }
+            }
+            Easing::ElasticInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin())
+                        / 2.0
+                } else {
+                    2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin()
+                        / 2.0
+                        + 1.0
+                }
+            }
+            Easing::BounceIn => 1.0 - Easing::BounceOut.apply(1.0 - t),
+            Easing::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984_375
+                }
+            }
+            Easing::BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - Easing::BounceOut.apply(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + Easing::BounceOut.apply(2.0 * t - 1.0)) / 2.0
+                }
+            }
+            Easing::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((BACK_IN_OUT + 1.0) * 2.0 * t - BACK_IN_OUT) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2)
+                        * ((BACK_IN_OUT + 1.0) * (t * 2.0 - 2.0) + BACK_IN_OUT)
+                        + 2.0)
+                        / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Values that can be interpolated by a [`Tween`].
+pub trait Lerp: Copy {
+    /// Interpolates from `self` to `other`, where `t` is usually between 0 and 1.
+    #[must_use]
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Vec3 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec3::lerp(self, other, t)
+    }
+}
+
+impl Lerp for Quat {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        let t = f64::from(t);
+        Color {
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
+            a: self.a + (other.a - self.a) * t,
+        }
+    }
+}
+
+impl Lerp for Transform {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// How many extra times a [`Tween`] plays after the first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeat {
+    Times(u32),
+    Forever,
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Self::Times(0)
+    }
+}
+
+/// Something that changes the game over time. The context is what the animation is applied to,
+/// which is the [`GameData`] for animations played by [`Tweens`].
+pub trait Animate<C = GameData> {
+    /// Moves the animation forward, returning the time left over from `delta` once it finishes or
+    /// `None` if it is still running.
+    fn advance(&mut self, delta: Duration, data: &mut C) -> Option<Duration>;
+    /// Returns the animation to its starting state.
+    fn reset(&mut self);
+}
+
+type Callback<C> = Box<dyn FnMut(&mut C)>;
+type Apply<T, C> = Box<dyn FnMut(&mut C, T)>;
+
+/// Interpolates a value with easing and hands it to a function every update.
+pub struct Tween<T: Lerp, C = GameData> {
+    pub from: T,
+    pub to: T,
+    pub duration: Duration,
+    pub easing: Easing,
+    /// How long to wait before starting.
+    pub delay: Duration,
+    pub repeat: Repeat,
+    /// Whether every other repeat plays backwards.
+    pub yoyo: bool,
+    apply: Apply<T, C>,
+    on_complete: Option<Callback<C>>,
+    delay_left: Duration,
+    elapsed: Duration,
+    iteration: u32,
+    finished: bool,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a tween that calls `apply` with the new value every update. Tweens with a duration
+    /// of zero finish immediately, even if they repeat.
+    #[must_use]
+    pub fn new(
+        from: T,
+        to: T,
+        duration: Duration,
+        apply: impl FnMut(&mut GameData, T) + 'static,
+    ) -> Self {
+        Self::with_context(from, to, duration, apply)
+    }
+}
+
+impl<T: Lerp, C> Tween<T, C> {
+    /// Creates a tween that applies its value to a context other than the [`GameData`], for
+    /// playing it outside of [`Tweens`].
+    #[must_use]
+    pub fn with_context(
+        from: T,
+        to: T,
+        duration: Duration,
+        apply: impl FnMut(&mut C, T) + 'static,
+    ) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::default(),
+            delay: Duration::ZERO,
+            repeat: Repeat::default(),
+            yoyo: false,
+            apply: Box::new(apply),
+            on_complete: None,
+            delay_left: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            iteration: 0,
+            finished: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_easing(self, easing: Easing) -> Self {
+        Self { easing, ..self }
+    }
+
+    #[must_use]
+    pub fn with_delay(self, delay: Duration) -> Self {
+        Self {
+            delay,
+            delay_left: delay,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_repeat(self, repeat: Repeat) -> Self {
+        Self { repeat, ..self }
+    }
+
+    #[must_use]
+    pub fn with_yoyo(self, yoyo: bool) -> Self {
+        Self { yoyo, ..self }
+    }
+
+    /// Calls `f` once the tween has finished.
+    #[must_use]
+    pub fn on_complete(self, f: impl FnMut(&mut C) + 'static) -> Self {
+        Self {
+            on_complete: Some(Box::new(f)),
+            ..self
+        }
+    }
+
+    /// The value of the tween at a point in the current repeat, between 0 and 1.
+    #[must_use]
+    pub fn value(&self, progress: f32) -> T {
+        let progress = if self.yoyo && self.iteration % 2 == 1 {
+            1.0 - progress
+        } else {
+            progress
+        };
+        self.from.lerp(self.to, self.easing.apply(progress))
+    }
+
+    fn repeats_left(&self) -> bool {
+        match self.repeat {
+            Repeat::Times(times) => self.iteration < times,
+            Repeat::Forever => true,
+        }
+    }
+
+    /// Moves the timing forward, returning the progress through the current repeat, or the time
+    /// left over if the tween finished.
+    fn step(&mut self, delta: Duration) -> Step {
+        if self.finished {
+            return Step::Finished(delta);
+        }
+        if delta < self.delay_left {
+            self.delay_left -= delta;
+            return Step::Waiting;
+        }
+        self.elapsed += delta - self.delay_left;
+        self.delay_left = Duration::ZERO;
+
+        while self.elapsed >= self.duration {
+            if self.duration.is_zero() || !self.repeats_left() {
+                self.finished = true;
+                return Step::Finished(self.elapsed - self.duration);
+            }
+            self.elapsed -= self.duration;
+            self.iteration += 1;
+        }
+        Step::Running(self.elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+}
+
+enum Step {
+    Waiting,
+    Running(f32),
+    Finished(Duration),
+}
+
+impl Tween<Vec3> {
+    /// Moves an instance of a sprite.
+    #[must_use]
+    pub fn translation(
+        sprite: SpriteHandle,
+        instance: usize,
+        from: Vec3,
+        to: Vec3,
+        duration: Duration,
+    ) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(sprite) = data.renderer.get_mut(sprite) {
+                if let Some(transform) = sprite.transforms.get_mut(instance) {
+                    transform.translation = value;
+                }
+            }
+        })
+    }
+
+    /// Scales an instance of a sprite.
+    #[must_use]
+    pub fn scale(
+        sprite: SpriteHandle,
+        instance: usize,
+        from: Vec3,
+        to: Vec3,
+        duration: Duration,
+    ) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(sprite) = data.renderer.get_mut(sprite) {
+                if let Some(transform) = sprite.transforms.get_mut(instance) {
+                    transform.scale = value;
+                }
+            }
+        })
+    }
+}
+
+impl Tween<Quat> {
+    /// Rotates an instance of a sprite.
+    #[must_use]
+    pub fn rotation(
+        sprite: SpriteHandle,
+        instance: usize,
+        from: Quat,
+        to: Quat,
+        duration: Duration,
+    ) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(sprite) = data.renderer.get_mut(sprite) {
+                if let Some(transform) = sprite.transforms.get_mut(instance) {
+                    transform.rotation = value;
+                }
+            }
+        })
+    }
+}
+
+impl Tween<Transform> {
+    /// Changes the whole transform of an instance of a sprite.
+    #[must_use]
+    pub fn transform(
+        sprite: SpriteHandle,
+        instance: usize,
+        from: Transform,
+        to: Transform,
+        duration: Duration,
+    ) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(sprite) = data.renderer.get_mut(sprite) {
+                if let Some(transform) = sprite.transforms.get_mut(instance) {
+                    *transform = value;
+                }
+            }
+        })
+    }
+}
+
+impl Tween<Vec2> {
+    /// Moves the camera of a viewport.
+    #[must_use]
+    pub fn camera(viewport: ViewportHandle, from: Vec2, to: Vec2, duration: Duration) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(viewport) = data.renderer.viewport_mut(viewport) {
+                viewport.camera.translation = value;
+            }
+        })
+    }
+}
+
+impl Tween<Color> {
+    /// Changes the tint of a sprite, see [`Sprite::set_tint`](crate::sprite::Sprite::set_tint).
+    #[must_use]
+    pub fn tint(sprite: SpriteHandle, from: Color, to: Color, duration: Duration) -> Self {
+        Self::new(from, to, duration, move |data, value| {
+            if let Some(sprite) = data.renderer.get_mut(sprite) {
+                sprite.set_tint(value);
+            }
+        })
+    }
+}
+
+impl<T: Lerp, C> Animate<C> for Tween<T, C> {
+    fn advance(&mut self, delta: Duration, data: &mut C) -> Option<Duration> {
+        let was_finished = self.finished;
+        match self.step(delta) {
+            Step::Waiting => None,
+            Step::Running(progress) => {
+                let value = self.value(progress);
+                (self.apply)(data, value);
+                None
+            }
+            Step::Finished(left) => {
+                if !was_finished {
+                    let value = self.value(1.0);
+                    (self.apply)(data, value);
+                    if let Some(on_complete) = &mut self.on_complete {
+                        on_complete(data);
+                    }
+                }
+                Some(left)
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.delay_left = self.delay;
+        self.elapsed = Duration::ZERO;
+        self.iteration = 0;
+        self.finished = false;
+    }
+}
+
+/// Waits before finishing, useful for pauses in a [`Sequence`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Wait {
+    pub duration: Duration,
+    elapsed: Duration,
+}
+
+impl Wait {
+    #[must_use]
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl<C> Animate<C> for Wait {
+    fn advance(&mut self, delta: Duration, _: &mut C) -> Option<Duration> {
+        self.elapsed += delta;
+        self.elapsed.checked_sub(self.duration)
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// Plays animations one after another.
+pub struct Sequence<C = GameData> {
+    animations: Vec<Box<dyn Animate<C>>>,
+    current: usize,
+    on_complete: Option<Callback<C>>,
+    finished: bool,
+}
+
+impl<C> Default for Sequence<C> {
+    fn default() -> Self {
+        Self {
+            animations: Vec::new(),
+            current: 0,
+            on_complete: None,
+            finished: false,
+        }
+    }
+}
+
+impl Sequence {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C> Sequence<C> {
+    #[must_use]
+    pub fn then(mut self, animation: impl Animate<C> + 'static) -> Self {
+        self.animations.push(Box::new(animation));
+        self
+    }
+
+    /// Calls `f` once every animation has finished.
+    #[must_use]
+    pub fn on_complete(self, f: impl FnMut(&mut C) + 'static) -> Self {
+        Self {
+            on_complete: Some(Box::new(f)),
+            ..self
+        }
+    }
+}
+
+impl<C> Animate<C> for Sequence<C> {
+    fn advance(&mut self, mut delta: Duration, data: &mut C) -> Option<Duration> {
+        if self.finished {
+            return Some(delta);
+        }
+        while let Some(animation) = self.animations.get_mut(self.current) {
+            delta = animation.advance(delta, data)?;
+            self.current += 1;
+        }
+        self.finished = true;
+        if let Some(on_complete) = &mut self.on_complete {
+            on_complete(data);
+        }
+        Some(delta)
+    }
+
+    fn reset(&mut self) {
+        self.animations
+            .iter_mut()
+            .for_each(|animation| animation.reset());
+        self.current = 0;
+        self.finished = false;
+    }
+}
+
+/// Plays animations at the same time, finishing once they all have.
+pub struct Parallel<C = GameData> {
+    animations: Vec<(Box<dyn Animate<C>>, bool)>,
+    on_complete: Option<Callback<C>>,
+    finished: bool,
+}
+
+impl<C> Default for Parallel<C> {
+    fn default() -> Self {
+        Self {
+            animations: Vec::new(),
+            on_complete: None,
+            finished: false,
+        }
+    }
+}
+
+impl Parallel {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C> Parallel<C> {
+    #[must_use]
+    pub fn with(mut self, animation: impl Animate<C> + 'static) -> Self {
+        self.animations.push((Box::new(animation), false));
+        self
+    }
+
+    /// Calls `f` once every animation has finished.
+    #[must_use]
+    pub fn on_complete(self, f: impl FnMut(&mut C) + 'static) -> Self {
+        Self {
+            on_complete: Some(Box::new(f)),
+            ..self
+        }
+    }
+}
+
+impl<C> Animate<C> for Parallel<C> {
+    fn advance(&mut self, delta: Duration, data: &mut C) -> Option<Duration> {
+        if self.finished {
+            return Some(delta);
+        }
+        let mut left = delta;
+        for (animation, finished) in self.animations.iter_mut().filter(|(_, done)| !*done) {
+            if let Some(animation_left) = animation.advance(delta, data) {
+                *finished = true;
+                left = left.min(animation_left);
+            }
+        }
+
+        if self.animations.iter().all(|(_, finished)| *finished) {
+            self.finished = true;
+            if let Some(on_complete) = &mut self.on_complete {
+                on_complete(data);
+            }
+            Some(left)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        for (animation, finished) in &mut self.animations {
+            animation.reset();
+            *finished = false;
+        }
+        self.finished = false;
+    }
+}
+
+/// A reference to an animation playing in [`Tweens`], which stops referring to anything once the
+/// animation finishes or is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TweenId {
+    index: usize,
+    generation: u32,
+}
+
+impl_handle!(TweenId);
+
+/// Animations that are advanced by [`GameData::delta_time`] after every update, and removed once
+/// finished.
+#[derive(Default)]
+pub struct Tweens {
+    // Animations are taken out while being advanced so that they can access the game data
+    animations: HandleSlab<TweenId, Option<Box<dyn Animate>>>,
+}
+
+impl Tweens {
+    pub fn add(&mut self, animation: impl Animate + 'static) -> TweenId {
+        self.animations.insert(Some(Box::new(animation)))
+    }
+
+    /// Stops an animation, returning whether it was still playing.
+    pub fn remove(&mut self, id: TweenId) -> bool {
+        self.animations.remove(id).is_some()
+    }
+
+    /// Whether an animation is still playing.
+    #[must_use]
+    pub fn contains(&self, id: TweenId) -> bool {
+        self.animations.contains(id)
+    }
+
+    pub fn clear(&mut self) {
+        self.animations.clear();
+    }
+
+    pub(crate) fn update(data: &mut GameData) {
+        let ids: Vec<_> = data.tweens.animations.iter().map(|(id, _)| id).collect();
+
+        for id in ids {
+            let Some(mut animation) = data
+                .tweens
+                .animations
+                .get_mut(id)
+                .and_then(|animation| animation.take())
+            else {
+                continue;
+            };
+
+            let finished = animation.advance(data.delta_time, data).is_some();
+            if finished {
+                data.tweens.animations.remove(id);
+            } else if let Some(slot) = data.tweens.animations.get_mut(id) {
+                *slot = Some(animation);
+            }
+        }
+    }
+}
+
+#[test]
+fn easing_test() {
+    for easing in [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ] {
+        assert!(
+            easing.apply(0.0).abs() < 1e-5,
+            "{easing:?} doesn't start at 0"
+        );
+        assert!(
+            (easing.apply(1.0) - 1.0).abs() < 1e-5,
+            "{easing:?} doesn't end at 1"
+        );
+    }
+    assert!((Easing::QuadInOut.apply(0.5) - 0.5).abs() < 1e-5);
+    assert!(Easing::BackIn.apply(0.2) < 0.0);
+    assert!(Easing::ElasticOut.apply(0.2) > 1.0);
+}
+
+#[test]
+fn tween_timing_test() {
+    let ms = Duration::from_millis;
+    let tween = || {
+        Tween::with_context(0.0, 10.0, ms(1000), |log: &mut Vec<f32>, value| {
+            log.push(value)
+        })
+    };
+    let assert_log = |log: &[f32], expected: &[f32]| {
+        assert_eq!(log.len(), expected.len(), "{log:?} != {expected:?}");
+        for (value, expected) in log.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-3, "{log:?} != {expected:?}");
+        }
+    };
+
+    let mut log = Vec::new();
+    let mut delayed = tween().with_delay(ms(500));
+    assert_eq!(delayed.advance(ms(250), &mut log), None);
+    assert_eq!(delayed.advance(ms(500), &mut log), None);
+    assert_log(&log, &[2.5]);
+
+    // Plays three times in total
+    let mut log = Vec::new();
+    let mut repeated = tween().with_repeat(Repeat::Times(2));
+    assert_eq!(repeated.advance(ms(2500), &mut log), None);
+    assert_eq!(repeated.advance(ms(1000), &mut log), Some(ms(500)));
+    assert_log(&log, &[5.0, 10.0]);
+
+    // Every other repeat plays backwards, so it ends where it started
+    let mut log = Vec::new();
+    let mut yoyo = tween().with_repeat(Repeat::Times(1)).with_yoyo(true);
+    yoyo.advance(ms(250), &mut log);
+    yoyo.advance(ms(1000), &mut log);
+    assert_eq!(yoyo.advance(ms(1000), &mut log), Some(ms(250)));
+    assert_log(&log, &[2.5, 7.5, 0.0]);
+
+    // Time left over from one animation carries into the next
+    let mut log = Vec::new();
+    let mut sequence = Sequence::default()
+        .then(tween())
+        .then(Wait::new(ms(500)))
+        .then(Tween::with_context(
+            10.0,
+            20.0,
+            ms(1000),
+            |log: &mut Vec<f32>, value| {
+                log.push(value);
+            },
+        ))
+        .on_complete(|log| log.push(-1.0));
+    assert_eq!(sequence.advance(ms(1750), &mut log), None);
+    assert_log(&log, &[10.0, 12.5]);
+    assert_eq!(sequence.advance(ms(1000), &mut log), Some(ms(250)));
+    assert_log(&log, &[10.0, 12.5, 20.0, -1.0]);
+    assert_eq!(sequence.advance(ms(100), &mut log), Some(ms(100)));
+    assert_eq!(log.len(), 4);
+
+    // Finishes with the longest animation
+    let mut log = Vec::new();
+    let mut parallel = Parallel::default()
+        .with(tween())
+        .with(Wait::new(ms(3000)))
+        .on_complete(|log: &mut Vec<f32>| log.push(-1.0));
+    assert_eq!(parallel.advance(ms(2000), &mut log), None);
+    assert_log(&log, &[10.0]);
+    assert_eq!(parallel.advance(ms(1500), &mut log), Some(ms(500)));
+    assert_log(&log, &[10.0, -1.0]);
+
+    parallel.reset();
+    assert_eq!(parallel.advance(ms(500), &mut log), None);
+    assert_log(&log, &[10.0, -1.0, 5.0]);
+}
@@ -0,0 +1,314 @@
+//! Areas of the window that are drawn through their own camera.
+
+use dirtytype::Dirty;
+use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    camera::Camera,
+    handle::impl_handle,
+    renderer::{Projection, RendererGlobals},
+    sprite::{Color, ColorVertex, Rect, Sprite},
+    transform::Transform,
+};
+
+/// A reference to a viewport in the [`Renderer`](crate::renderer::Renderer), which stops
+/// referring to anything once the viewport is removed, even if the slot is reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ViewportHandle {
+    index: usize,
+    generation: u32,
+}
+
+impl_handle!(ViewportHandle);
+
+/// A camera and projection that draws sprites to an area of the window.
+pub struct Viewport {
+    pub camera: Dirty<Camera>,
+    pub projection: Dirty<Projection>,
+    /// The area of the window to draw to, where `(0, 0)` is the top left of the window and
+    /// `(1, 1)` is the bottom right.
+    pub rect: Dirty<Rect>,
+    /// Only sprites with one of these layer bits set are drawn.
+    pub layers: u32,
+    /// If set, the viewport shrinks to the fixed area of its projection, keeping the aspect ratio,
+    /// and the space around it is filled with this color.
+    pub letterbox: Option<Color>,
+    letterbox_bars: Sprite,
+    letterbox_color: Color,
+    pub(crate) identity_bind_group: wgpu::BindGroup,
+    camera_buffer: wgpu::Buffer,
+    projection_buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+    screen_buffer: wgpu::Buffer,
+    pub(crate) screen_bind_group: wgpu::BindGroup,
+    window_size: PhysicalSize<u32>,
+    active_size: Vec2,
+}
+
+impl Viewport {
+    /// Creates a viewport that covers the whole window and draws every layer.
+    #[must_use]
+    pub fn new(camera: Camera, projection: Projection) -> Self {
+        let projection_buffer = Self::matrix_buffer(Mat4::IDENTITY);
+        let camera_buffer = Self::matrix_buffer(Mat4::from(&camera));
+        let bind_group = Self::bind_group(&projection_buffer, &camera_buffer);
+        let screen_buffer = Self::matrix_buffer(Mat4::IDENTITY);
+        // The camera of screen space is always the identity, so the buffer doesn't need to be kept
+        let screen_bind_group =
+            Self::bind_group(&screen_buffer, &Self::matrix_buffer(Mat4::IDENTITY));
+
+        let letterbox_color = Color::BLACK;
+        let letterbox_bars =
+            Sprite::new_color(letterbox_color, vec![Transform::scale(vec3(2.0, 2.0, 1.0))]);
+        let identity_bind_group = Self::bind_group(
+            &Self::matrix_buffer(Mat4::IDENTITY),
+            &Self::matrix_buffer(Mat4::IDENTITY),
+        );
+
+        Self {
+            camera: Dirty::new(camera),
+            // Dirty so that the projection is uploaded once the window size is known
+            projection: Dirty {
+                data: projection,
+                dirty: true,
+            },
+            rect: Dirty::new(Rect::default()),
+            layers: u32::MAX,
+            letterbox: None,
+            letterbox_bars,
+            letterbox_color,
+            identity_bind_group,
+            camera_buffer,
+            projection_buffer,
+            bind_group,
+            screen_buffer,
+            screen_bind_group,
+            window_size: PhysicalSize::default(),
+            active_size: Vec2::ZERO,
+        }
+    }
+
+    #[must_use]
+    pub fn with_rect(mut self, rect: Rect) -> Self {
+        *self.rect = rect;
+        self
+    }
+
+    #[must_use]
+    pub fn with_layers(self, layers: u32) -> Self {
+        Self { layers, ..self }
+    }
+
+    #[must_use]
+    pub fn with_letterbox(self, color: Color) -> Self {
+        Self {
+            letterbox: Some(color),
+            ..self
+        }
+    }
+
+    /// The area of the window the viewport draws to, in physical pixels.
+    #[must_use]
+    pub fn pixel_rect(&self, window_size: PhysicalSize<u32>) -> Rect {
+        let window_size = vec2(window_size.width as f32, window_size.height as f32);
+        let min = (self.rect.pos * window_size)
+            .round()
+            .clamp(Vec2::ZERO, window_size);
+        let max = ((self.rect.pos + self.rect.size) * window_size)
+            .round()
+            .clamp(min, window_size);
+        Rect {
+            pos: min,
+            size: max - min,
+        }
+    }
+
+    /// The area of the window sprites are drawn to in physical pixels, which is smaller than
+    /// [`Viewport::pixel_rect`] when letterboxed. Input positions should be mapped into this.
+    #[must_use]
+    pub fn active_rect(&self, window_size: PhysicalSize<u32>) -> Rect {
+        let rect = self.pixel_rect(window_size);
+        let Some(size) = self
+            .letterbox
+            .and_then(|_| self.projection.fixed_area(Self::physical_size(rect)))
+        else {
+            return rect;
+        };
+        let size = size.round();
+        Rect {
+            pos: (rect.pos + (rect.size - size) / 2.0).floor(),
+            size,
+        }
+    }
+
+    /// Whether a position on the window in physical pixels is inside the active area of the
+    /// viewport.
+    #[must_use]
+    pub fn contains(&self, window_size: PhysicalSize<u32>, pos: Vec2) -> bool {
+        self.active_rect(window_size).contains(pos)
+    }
+
+    /// Converts a position on the window in physical pixels to a position in the world.
+    #[must_use]
+    pub fn screen_to_world(&self, window_size: PhysicalSize<u32>, pos: Vec2) -> Vec2 {
+        let rect = self.active_rect(window_size);
+        let relative = (pos - rect.pos) / rect.size;
+        let ndc = vec2(relative.x * 2.0 - 1.0, 1.0 - relative.y * 2.0);
+        self.view_matrix(window_size)
+            .inverse()
+            .project_point3(ndc.extend(0.0))
+            .truncate()
+    }
+
+    /// Converts a position in the world to a position on the window in physical pixels.
+    #[must_use]
+    pub fn world_to_screen(&self, window_size: PhysicalSize<u32>, pos: Vec3) -> Vec2 {
+        let rect = self.active_rect(window_size);
+        let ndc = self.view_matrix(window_size).project_point3(pos);
+        rect.pos + vec2(ndc.x + 1.0, 1.0 - ndc.y) / 2.0 * rect.size
+    }
+
+    /// Converts a position on the window in physical pixels to a position in
+    /// [`Space::Screen`](crate::sprite::Space::Screen).
+    #[must_use]
+    pub fn screen_to_pixels(&self, window_size: PhysicalSize<u32>, pos: Vec2) -> Vec2 {
+        let rect = self.active_rect(window_size);
+        vec2(pos.x - rect.pos.x, rect.pos.y + rect.size.y - pos.y)
+    }
+
+    /// The size of the area of the world that is visible through the camera.
+    #[must_use]
+    pub fn visible_size(&self, window_size: PhysicalSize<u32>) -> Vec2 {
+        self.projection
+            .visible_size(Self::physical_size(self.active_rect(window_size)))
+            / self.camera.zoom
+    }
+
+    fn view_matrix(&self, window_size: PhysicalSize<u32>) -> Mat4 {
+        self.projection
+            .as_mat4(Self::physical_size(self.active_rect(window_size)))
+            * Mat4::from(&*self.camera)
+    }
+
+    fn physical_size(rect: Rect) -> PhysicalSize<u32> {
+        PhysicalSize::new(rect.size.x.max(1.0) as u32, rect.size.y.max(1.0) as u32)
+    }
+
+    /// Writes the camera and projection to their buffers if they have changed.
+    pub(crate) fn upload(&mut self, window_size: PhysicalSize<u32>) {
+        // Can't use `Dirty::clean` because it requires weird mutability issues
+        if self.camera.dirty {
+            RendererGlobals::get().queue.write_buffer(
+                &self.camera_buffer,
+                0,
+                bytemuck::cast_slice(&[Mat4::from(&*self.camera)]),
+            );
+            self.camera.dirty = false;
+        }
+        let active_rect = self.active_rect(window_size);
+        if self.projection.dirty
+            || self.rect.dirty
+            || self.window_size != window_size
+            || self.active_size != active_rect.size
+        {
+            RendererGlobals::get().queue.write_buffer(
+                &self.projection_buffer,
+                0,
+                bytemuck::cast_slice(&[self
+                    .projection
+                    .as_mat4(Self::physical_size(self.active_rect(window_size)))]),
+            );
+            let size = self.active_rect(window_size).size;
+            RendererGlobals::get().queue.write_buffer(
+                &self.screen_buffer,
+                0,
+                bytemuck::cast_slice(&[Mat4::orthographic_rh(
+                    0.0,
+                    size.x,
+                    0.0,
+                    size.y,
+                    self.projection.near,
+                    self.projection.far,
+                )]),
+            );
+            self.projection.dirty = false;
+            self.rect.dirty = false;
+            self.window_size = window_size;
+            self.active_size = active_rect.size;
+        }
+        if let Some(color) = self.letterbox {
+            if color != self.letterbox_color {
+                self.letterbox_bars.set_vertices(&ColorVertex::quad(color));
+                self.letterbox_color = color;
+            }
+        }
+    }
+
+    /// The sprite that fills the letterbox bars, if letterboxing is enabled.
+    pub(crate) fn letterbox_bars(&self) -> Option<&Sprite> {
+        self.letterbox.map(|_| &self.letterbox_bars)
+    }
+
+    fn matrix_buffer(matrix: Mat4) -> wgpu::Buffer {
+        RendererGlobals::get()
+            .device
+            .create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&[matrix]),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            })
+    }
+
+    fn bind_group(projection: &wgpu::Buffer, camera: &wgpu::Buffer) -> wgpu::BindGroup {
+        RendererGlobals::get()
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("projection"),
+                layout: &Self::bind_group_layout(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(
+                            projection.as_entire_buffer_binding(),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Buffer(camera.as_entire_buffer_binding()),
+                    },
+                ],
+            })
+    }
+
+    pub(crate) fn bind_group_layout() -> wgpu::BindGroupLayout {
+        RendererGlobals::get()
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+    }
+}
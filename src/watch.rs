@@ -0,0 +1,81 @@
+//! Polling files for changes, for hot reloading during development.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use fxhash::FxHashMap;
+
+/// Checks the modification times of files at most once per interval.
+#[derive(Debug)]
+pub(crate) struct FileWatcher {
+    interval: Duration,
+    last_check: Instant,
+    files: FxHashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_check: Instant::now(),
+            files: FxHashMap::default(),
+        }
+    }
+
+    /// Starts watching a file from its current modification time.
+    pub(crate) fn watch(&mut self, path: &Path) {
+        if !self.files.contains_key(path) {
+            self.files.insert(path.to_owned(), modified(path));
+        }
+    }
+
+    pub(crate) fn unwatch(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// The files that have been modified since the last check. Files that are missing, such as
+    /// while an editor replaces them, are checked again next time.
+    pub(crate) fn changed(&mut self) -> Vec<PathBuf> {
+        if self.last_check.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+
+        let mut changed = Vec::new();
+        for (path, time) in &mut self.files {
+            let modified = modified(path);
+            if modified.is_some() && modified != *time {
+                *time = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[test]
+fn file_watcher_test() {
+    let path = std::env::temp_dir().join(format!("rengine-watch-{}", std::process::id()));
+    fs::write(&path, "a").unwrap();
+    let mut watcher = FileWatcher::new(Duration::ZERO);
+    watcher.watch(&path);
+    assert!(watcher.changed().is_empty());
+
+    let file = fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(watcher.changed(), vec![path.clone()]);
+    assert!(watcher.changed().is_empty());
+
+    watcher.unwatch(&path);
+    fs::remove_file(&path).unwrap();
+}